@@ -0,0 +1,75 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use rusty_chess::field::{Field, Figure, FigureColor, FigureType};
+
+static FIGURE_TYPES: [FigureType; 6] = [
+    FigureType::Pawn,
+    FigureType::Rook,
+    FigureType::Knight,
+    FigureType::Bishop,
+    FigureType::Queen,
+    FigureType::King,
+];
+
+// maps a byte to one of the twelve figures or an empty square
+fn figure_from_byte(byte: u8) -> Option<Figure> {
+    let index = (byte % 16) as usize;
+    if index >= 12 {
+        return None;
+    }
+    let color = if index < 6 {
+        FigureColor::White
+    } else {
+        FigureColor::Black
+    };
+    Some(Figure::new(color, FIGURE_TYPES[index % 6]))
+}
+
+// coordinates cover the board, just off the board and the overflow edge
+fn coordinate_from_byte(byte: u8) -> u32 {
+    match byte {
+        255 => u32::MAX,
+        _ => (byte % 12) as u32,
+    }
+}
+
+// the first 64 bytes describe an arbitrary (often kingless or otherwise
+// broken) position, the rest are coordinates fed to every public method
+// of Field, none of which may panic
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        if let Some((field, to_move)) = Field::from_fen(text) {
+            let _ = field.validate(to_move);
+        }
+    }
+
+    let mut field = Field::new();
+    for (i, byte) in data.iter().take(64).enumerate() {
+        field.figures[i] = figure_from_byte(*byte);
+    }
+
+    for color in [FigureColor::White, FigureColor::Black] {
+        let _ = field.is_check(color);
+        let _ = field.validate(color);
+        field.is_checkmate(color);
+    }
+    field.is_draw();
+
+    let coordinates: Vec<u32> =
+        data.iter().skip(64).map(|b| coordinate_from_byte(*b)).collect();
+    for chunk in coordinates.chunks_exact(4) {
+        let (x, y, to_x, to_y) = (chunk[0], chunk[1], chunk[2], chunk[3]);
+
+        field.get(x, y);
+        field.get_possible_moves(x, y, FigureColor::White);
+        field.get_possible_moves(x, y, FigureColor::Black);
+        let _ = field.move_figure(x, y, to_x, to_y);
+        if let Some(figure) = figure_from_byte(to_x as u8) {
+            let _ = field.set(x, y, figure);
+        } else {
+            let _ = field.clear(x, y);
+        }
+    }
+});
@@ -0,0 +1,135 @@
+use crate::field::{parse_square, square_name};
+use crate::notation::parse_move;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnnotationColor {
+    Green,
+    Red,
+    Blue,
+    Yellow,
+}
+
+impl AnnotationColor {
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            AnnotationColor::Green => (21, 120, 27),
+            AnnotationColor::Red => (136, 32, 32),
+            AnnotationColor::Blue => (0, 48, 136),
+            AnnotationColor::Yellow => (230, 143, 0),
+        }
+    }
+
+    fn letter(self) -> char {
+        match self {
+            AnnotationColor::Green => 'G',
+            AnnotationColor::Red => 'R',
+            AnnotationColor::Blue => 'B',
+            AnnotationColor::Yellow => 'Y',
+        }
+    }
+}
+
+// arrows and circled squares drawn over the board, e.g. while coaching
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Annotation {
+    Arrow {
+        from: (u32, u32),
+        to: (u32, u32),
+        color: AnnotationColor,
+    },
+    Circle {
+        square: (u32, u32),
+        color: AnnotationColor,
+    },
+}
+
+impl Annotation {
+    // a drag ending on the square it started on circles the square
+    pub fn from_drag(
+        from: (u32, u32),
+        to: (u32, u32),
+        color: AnnotationColor,
+    ) -> Self {
+        if from == to {
+            Annotation::Circle {
+                square: from,
+                color,
+            }
+        } else {
+            Annotation::Arrow { from, to, color }
+        }
+    }
+
+    // parses the notation of lichess' [%cal] and [%csl] comments, a color
+    // letter followed by one or two squares, e.g. "Ge2e4" or "Rd5"
+    pub fn parse(text: &str) -> Option<Self> {
+        let color = match text.get(..1)? {
+            "G" => AnnotationColor::Green,
+            "R" => AnnotationColor::Red,
+            "B" => AnnotationColor::Blue,
+            "Y" => AnnotationColor::Yellow,
+            _ => return None,
+        };
+        let squares = text.get(1..)?;
+        if let Some(square) = parse_square(squares) {
+            return Some(Annotation::Circle { square, color });
+        }
+        let (from, to) = parse_move(squares)?;
+        Some(Annotation::from_drag(from, to, color))
+    }
+
+    // the inverse of parse, e.g. "Ge2e4" or "Rd5"
+    pub fn text(&self) -> String {
+        match *self {
+            Annotation::Arrow { from, to, color } => format!(
+                "{}{}{}",
+                color.letter(),
+                square_name(from.0, from.1),
+                square_name(to.0, to.1)
+            ),
+            Annotation::Circle { square, color } => {
+                format!("{}{}", color.letter(), square_name(square.0, square.1))
+            }
+        }
+    }
+
+    pub fn color(&self) -> AnnotationColor {
+        match self {
+            Annotation::Arrow { color, .. } => *color,
+            Annotation::Circle { color, .. } => *color,
+        }
+    }
+
+    fn same_squares(&self, other: &Annotation) -> bool {
+        match (self, other) {
+            (
+                Annotation::Arrow { from, to, .. },
+                Annotation::Arrow {
+                    from: other_from,
+                    to: other_to,
+                    ..
+                },
+            ) => from == other_from && to == other_to,
+            (
+                Annotation::Circle { square, .. },
+                Annotation::Circle {
+                    square: other_square,
+                    ..
+                },
+            ) => square == other_square,
+            _ => false,
+        }
+    }
+}
+
+// drawing an annotation again removes it, drawing it in another color
+// replaces the old one
+pub fn toggle(annotations: &mut Vec<Annotation>, annotation: Annotation) {
+    match annotations.iter().position(|a| a.same_squares(&annotation)) {
+        Some(index) if annotations[index] == annotation => {
+            annotations.remove(index);
+        }
+        Some(index) => annotations[index] = annotation,
+        None => annotations.push(annotation),
+    }
+}
@@ -0,0 +1,45 @@
+use crate::field::{Figure, FigureColor, FigureType};
+
+// the default piece set, font and themes are compiled into the binary, so
+// it runs from any working directory
+
+pub static FONT: &[u8] = include_bytes!("../resources/C64_Pro-STYLE.ttf");
+pub static FONT_NAME: &str = "C64_Pro-STYLE.ttf";
+pub static THEMES: &str = include_str!("../resources/themes.toml");
+
+// file name of a figure's sprite in a piece set, e.g. "w_pawn.png"
+pub fn sprite_name(figure: &Figure) -> String {
+    let color = match figure.color {
+        FigureColor::White => "w",
+        FigureColor::Black => "b",
+    };
+    let figure_type = match figure.figure_type {
+        FigureType::Pawn => "pawn",
+        FigureType::Rook => "rook",
+        FigureType::Knight => "knight",
+        FigureType::Bishop => "bishop",
+        FigureType::Queen => "queen",
+        FigureType::King => "king",
+    };
+    format!("{}_{}.png", color, figure_type)
+}
+
+// embedded sprite by file name, e.g. "w_pawn.png"
+pub fn sprite(name: &str) -> Option<&'static [u8]> {
+    let bytes: &[u8] = match name {
+        "w_pawn.png" => include_bytes!("../resources/sprites/w_pawn.png"),
+        "w_rook.png" => include_bytes!("../resources/sprites/w_rook.png"),
+        "w_knight.png" => include_bytes!("../resources/sprites/w_knight.png"),
+        "w_bishop.png" => include_bytes!("../resources/sprites/w_bishop.png"),
+        "w_queen.png" => include_bytes!("../resources/sprites/w_queen.png"),
+        "w_king.png" => include_bytes!("../resources/sprites/w_king.png"),
+        "b_pawn.png" => include_bytes!("../resources/sprites/b_pawn.png"),
+        "b_rook.png" => include_bytes!("../resources/sprites/b_rook.png"),
+        "b_knight.png" => include_bytes!("../resources/sprites/b_knight.png"),
+        "b_bishop.png" => include_bytes!("../resources/sprites/b_bishop.png"),
+        "b_queen.png" => include_bytes!("../resources/sprites/b_queen.png"),
+        "b_king.png" => include_bytes!("../resources/sprites/b_king.png"),
+        _ => return None,
+    };
+    Some(bytes)
+}
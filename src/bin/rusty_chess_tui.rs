@@ -0,0 +1,248 @@
+// text mode front end, playable over ssh without a display
+//
+// shares the rules and move notation with the sdl client but does not link
+// sdl at all
+
+use rusty_chess::field::{Field, Figure, FigureColor, FigureType};
+use rusty_chess::game::{Game, Outcome};
+use rusty_chess::notation;
+
+use std::io::{self, BufRead, Write};
+
+// 256 color palette entries of the board
+static LIGHT_SQUARE: u8 = 180;
+static DARK_SQUARE: u8 = 137;
+static LAST_MOVE: u8 = 186;
+static CHECK: u8 = 167;
+
+const HELP: &str = "\
+Type a move in algebraic (Nf3, exd5) or coordinate (g1f3) notation.
+  moves        list the legal moves
+  flip         turn the board around
+  new          start a new game
+  fen <fen>    set up a position
+  help         show this help
+  quit         leave";
+
+fn glyph(figure: &Figure) -> char {
+    // the filled glyphs for both colors, the color is set by the terminal
+    match figure.figure_type {
+        FigureType::Pawn => '♟',
+        FigureType::Rook => '♜',
+        FigureType::Knight => '♞',
+        FigureType::Bishop => '♝',
+        FigureType::Queen => '♛',
+        FigureType::King => '♚',
+    }
+}
+
+// `flipped` puts black at the bottom
+fn render_board(game: &Game, flipped: bool) -> String {
+    let check_square = if game.field.is_check(game.to_move) == Ok(true) {
+        game.field.find_king(game.to_move)
+    } else {
+        None
+    };
+
+    let files: Vec<u32> = if flipped {
+        (0..8).rev().collect()
+    } else {
+        (0..8).collect()
+    };
+    let ranks = files.clone();
+
+    let mut out = String::new();
+    for &y in &ranks {
+        out.push_str(&format!(" {} ", 8 - y));
+        for &x in &files {
+            let last_move = game
+                .last_move
+                .is_some_and(|(from, to)| from == (x, y) || to == (x, y));
+            let background = if check_square == Some((x, y)) {
+                CHECK
+            } else if last_move {
+                LAST_MOVE
+            } else if (x + y) % 2 == 0 {
+                LIGHT_SQUARE
+            } else {
+                DARK_SQUARE
+            };
+
+            out.push_str(&format!("\x1b[48;5;{}m", background));
+            match game.field.get(x, y) {
+                Some(figure) => {
+                    let foreground = match figure.color {
+                        FigureColor::White => 231,
+                        FigureColor::Black => 16,
+                    };
+                    out.push_str(&format!(
+                        "\x1b[38;5;{}m {} ",
+                        foreground,
+                        glyph(figure)
+                    ));
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str("\x1b[0m\n");
+    }
+
+    out.push_str("   ");
+    for &x in &files {
+        out.push_str(&format!(" {} ", (b'a' + x as u8) as char));
+    }
+    out.push('\n');
+    out
+}
+
+fn render_history(game: &Game) -> String {
+    // a black first move is numbered "1..."
+    let history = game.san_history();
+    let mut moves = history.iter();
+    let mut lines = Vec::new();
+    let mut number = 1;
+    if game.start.1 == FigureColor::Black {
+        if let Some(san) = moves.next() {
+            lines.push(format!("1... {}", san));
+            number += 1;
+        }
+    }
+    while let Some(white) = moves.next() {
+        match moves.next() {
+            Some(black) => {
+                lines.push(format!("{}. {} {}", number, white, black))
+            }
+            None => lines.push(format!("{}. {}", number, white)),
+        }
+        number += 1;
+    }
+    lines.join(" ")
+}
+
+fn render_status(game: &Game) -> String {
+    let name = |color| match color {
+        FigureColor::White => "White",
+        FigureColor::Black => "Black",
+    };
+    match game.outcome {
+        Some(Outcome::Checkmate(winner)) => {
+            format!("Checkmate, {} wins", name(winner))
+        }
+        Some(Outcome::Stalemate) => "Stalemate".to_string(),
+        Some(Outcome::Draw) => "Draw".to_string(),
+        None if game.field.is_check(game.to_move) == Ok(true) => {
+            format!("{} to move, check!", name(game.to_move))
+        }
+        None => format!("{} to move", name(game.to_move)),
+    }
+}
+
+fn render(game: &Game, flipped: bool) -> String {
+    let captured = |figures: &[Figure]| figures.iter().map(glyph).collect();
+    let captured_white: String = captured(&game.captured_white);
+    let captured_black: String = captured(&game.captured_black);
+
+    let mut out = render_board(game, flipped);
+    if !captured_white.is_empty() || !captured_black.is_empty() {
+        out.push_str(&format!(
+            "Captured: white {} black {}\n",
+            captured_white, captured_black
+        ));
+    }
+    if !game.history.is_empty() {
+        out.push_str(&render_history(game));
+        out.push('\n');
+    }
+    out.push_str(&render_status(game));
+    out.push('\n');
+    out
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut game = Game::new(Field::get_start_position(), FigureColor::White);
+    let mut flipped = false;
+
+    println!("Rusty Chess, type `help` for the commands");
+    print!("{}", render(&game, flipped));
+
+    loop {
+        print!("> ");
+        stdout.flush().ok();
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            // end of input
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("could not read input: {}", e);
+                break;
+            }
+        }
+
+        let line = line.trim();
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "" => continue,
+            "quit" | "exit" => break,
+            "help" => {
+                println!("{}", HELP);
+                continue;
+            }
+            "moves" => {
+                let moves: Vec<String> =
+                    notation::suggestions(&game.field, game.to_move, "")
+                        .into_iter()
+                        .map(|(_, san)| san)
+                        .collect();
+                println!("{}", moves.join(" "));
+                continue;
+            }
+            "flip" => flipped = !flipped,
+            "new" => {
+                game =
+                    Game::new(Field::get_start_position(), FigureColor::White)
+            }
+            "fen" => match Field::from_fen(argument) {
+                Some((field, to_move)) => match field.validate(to_move) {
+                    Ok(()) => game = Game::new(field, to_move),
+                    Err(e) => {
+                        println!("{}", e);
+                        continue;
+                    }
+                },
+                None => {
+                    println!("invalid fen: {}", argument);
+                    continue;
+                }
+            },
+            _ if game.outcome.is_some() => {
+                println!("{}, type `new` to play again", render_status(&game));
+                continue;
+            }
+            _ => match notation::parse(&game.field, game.to_move, line) {
+                Some(mv) => {
+                    // parse only returns legal moves
+                    game.play(mv).ok();
+                }
+                None => {
+                    let suggestions: Vec<String> =
+                        notation::suggestions(&game.field, game.to_move, line)
+                            .into_iter()
+                            .map(|(_, san)| san)
+                            .collect();
+                    if suggestions.is_empty() {
+                        println!("no legal move {}", line);
+                    } else {
+                        println!("did you mean {}", suggestions.join(" "));
+                    }
+                    continue;
+                }
+            },
+        }
+
+        print!("{}", render(&game, flipped));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::field::{
+    self, Field, Figure, FigureColor, FigureType, PositionError,
+};
+
+// the figures offered in the palette, white on the first row, black below
+pub static PALETTE: [Figure; 12] = [
+    Figure {
+        color: FigureColor::White,
+        figure_type: FigureType::King,
+    },
+    Figure {
+        color: FigureColor::White,
+        figure_type: FigureType::Queen,
+    },
+    Figure {
+        color: FigureColor::White,
+        figure_type: FigureType::Rook,
+    },
+    Figure {
+        color: FigureColor::White,
+        figure_type: FigureType::Bishop,
+    },
+    Figure {
+        color: FigureColor::White,
+        figure_type: FigureType::Knight,
+    },
+    Figure {
+        color: FigureColor::White,
+        figure_type: FigureType::Pawn,
+    },
+    Figure {
+        color: FigureColor::Black,
+        figure_type: FigureType::King,
+    },
+    Figure {
+        color: FigureColor::Black,
+        figure_type: FigureType::Queen,
+    },
+    Figure {
+        color: FigureColor::Black,
+        figure_type: FigureType::Rook,
+    },
+    Figure {
+        color: FigureColor::Black,
+        figure_type: FigureType::Bishop,
+    },
+    Figure {
+        color: FigureColor::Black,
+        figure_type: FigureType::Knight,
+    },
+    Figure {
+        color: FigureColor::Black,
+        figure_type: FigureType::Pawn,
+    },
+];
+
+// castling rights in FEN order: K, Q, k, q
+// (king and rook squares of each right)
+static CASTLING_SQUARES: [((u32, u32), (u32, u32)); 4] = [
+    ((4, 7), (7, 7)),
+    ((4, 7), (0, 7)),
+    ((4, 0), (7, 0)),
+    ((4, 0), (0, 0)),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Editor {
+    pub selected: Option<Figure>,
+    pub castling: [bool; 4],
+    pub en_passant: Option<(u32, u32)>,
+    pub error: Option<PositionError>,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Self {
+            selected: None,
+            castling: [false; 4],
+            en_passant: None,
+            error: None,
+        }
+    }
+
+    // places the selected figure, clicking a square holding the same figure
+    // (or any square without a selection) clears it
+    pub fn click_square(&mut self, field: &mut Field, x: u32, y: u32) {
+        let result = match self.selected {
+            Some(figure) if field.get(x, y) != Some(&figure) => {
+                field.set(x, y, figure)
+            }
+            _ => field.clear(x, y),
+        };
+        self.error = result.err();
+    }
+
+    pub fn toggle_en_passant(&mut self, x: u32, y: u32) {
+        if self.en_passant == Some((x, y)) {
+            self.en_passant = None;
+        } else {
+            self.en_passant = Some((x, y));
+        }
+        self.error = None;
+    }
+
+    pub fn toggle_castling(&mut self, index: usize) {
+        self.castling[index] = !self.castling[index];
+        self.error = None;
+    }
+
+    pub fn castling_text(&self) -> String {
+        field::castling_text(self.castling)
+    }
+
+    pub fn validate(
+        &self,
+        field: &Field,
+        to_move: FigureColor,
+    ) -> Result<(), PositionError> {
+        field.validate(to_move)?;
+
+        // every castling right needs its king and rook on their home squares
+        for (index, (king, rook)) in CASTLING_SQUARES.iter().enumerate() {
+            if !self.castling[index] {
+                continue;
+            }
+            let color = if index < 2 {
+                FigureColor::White
+            } else {
+                FigureColor::Black
+            };
+            let in_place = |(x, y): (u32, u32), figure_type| {
+                field.get(x, y) == Some(&Figure::new(color, figure_type))
+            };
+            if !in_place(*king, FigureType::King)
+                || !in_place(*rook, FigureType::Rook)
+            {
+                return Err(PositionError::InvalidCastling);
+            }
+        }
+
+        // the en passant square lies behind a pawn that just moved two squares
+        if let Some((x, y)) = self.en_passant {
+            let (ep_rank, pawn_rank, start_rank, pawn_color) = match to_move {
+                FigureColor::White => (2, 3, 1, FigureColor::Black),
+                FigureColor::Black => (5, 4, 6, FigureColor::White),
+            };
+            let valid = y == ep_rank
+                && field.get(x, y).is_none()
+                && field.get(x, start_rank).is_none()
+                && field.get(x, pawn_rank)
+                    == Some(&Figure::new(pawn_color, FigureType::Pawn));
+            if !valid {
+                return Err(PositionError::InvalidEnPassant);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::fmt;
 
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
@@ -29,11 +30,97 @@ impl Figure {
     }
 }
 
+pub fn opposite(color: FigureColor) -> FigureColor {
+    match color {
+        FigureColor::White => FigureColor::Black,
+        FigureColor::Black => FigureColor::White,
+    }
+}
+
+// name of a square in algebraic notation, e.g. "e4"
+pub fn square_name(x: u32, y: u32) -> String {
+    format!("{}{}", (x as u8 + b'a') as char, 8 - y)
+}
+
+// castling rights in FEN order (K, Q, k, q) as FEN text, e.g. "Kq" or "-"
+pub fn castling_text(castling: [bool; 4]) -> String {
+    let text: String = "KQkq"
+        .chars()
+        .zip(castling)
+        .filter(|&(_, allowed)| allowed)
+        .map(|(c, _)| c)
+        .collect();
+    if text.is_empty() {
+        "-".to_string()
+    } else {
+        text
+    }
+}
+
+// parses a square name, e.g. "e4"
+pub fn parse_square(text: &str) -> Option<(u32, u32)> {
+    match *text.as_bytes() {
+        [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+            Some(((file - b'a') as u32, (b'8' - rank) as u32))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PositionError {
+    MissingKing(FigureColor),
+    TooManyKings(FigureColor),
+    PawnOnBackRank(u32, u32),
+    OpponentInCheck,
+    InvalidCastling,
+    InvalidEnPassant,
+    OffBoard(u32, u32),
+    EmptySquare(u32, u32),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let color_name = |color: &FigureColor| match color {
+            FigureColor::White => "white",
+            FigureColor::Black => "black",
+        };
+        match self {
+            PositionError::MissingKing(color) => {
+                write!(f, "No {} king", color_name(color))
+            }
+            PositionError::TooManyKings(color) => {
+                write!(f, "Too many {} kings", color_name(color))
+            }
+            PositionError::PawnOnBackRank(..) => {
+                write!(f, "Pawn on back rank")
+            }
+            PositionError::OpponentInCheck => {
+                write!(f, "Side not to move in check")
+            }
+            PositionError::InvalidCastling => {
+                write!(f, "Invalid castling rights")
+            }
+            PositionError::InvalidEnPassant => {
+                write!(f, "Invalid en passant")
+            }
+            PositionError::OffBoard(..) => write!(f, "Square off the board"),
+            PositionError::EmptySquare(..) => write!(f, "No piece to move"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct Field {
     pub figures: [Option<Figure>; 64],
 }
 
+impl Default for Field {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Field {
     pub fn new() -> Self {
         Self {
@@ -41,12 +128,34 @@ impl Field {
         }
     }
 
+    // index into `figures`, or None for squares off the board
+    fn index(x: u32, y: u32) -> Option<usize> {
+        if x < 8 && y < 8 {
+            Some((x + y * 8) as usize)
+        } else {
+            None
+        }
+    }
+
     pub fn get(&self, x: u32, y: u32) -> Option<&Figure> {
-        self.figures.get((x + y * 8) as usize).unwrap().as_ref()
+        self.figures[Self::index(x, y)?].as_ref()
     }
 
-    pub fn set(&mut self, x: u32, y: u32, figure: Figure) {
-        self.figures[(x + y * 8) as usize] = Some(figure);
+    pub fn set(
+        &mut self,
+        x: u32,
+        y: u32,
+        figure: Figure,
+    ) -> Result<(), PositionError> {
+        let index = Self::index(x, y).ok_or(PositionError::OffBoard(x, y))?;
+        self.figures[index] = Some(figure);
+        Ok(())
+    }
+
+    pub fn clear(&mut self, x: u32, y: u32) -> Result<(), PositionError> {
+        let index = Self::index(x, y).ok_or(PositionError::OffBoard(x, y))?;
+        self.figures[index] = None;
+        Ok(())
     }
 
     pub fn move_figure(
@@ -55,39 +164,188 @@ impl Field {
         from_y: u32,
         to_x: u32,
         to_y: u32,
-    ) {
-        let figure = self.get(from_x, from_y).unwrap().clone();
-        self.set(to_x, to_y, figure);
-        self.figures[(from_x + from_y * 8) as usize] = None;
+    ) -> Result<(), PositionError> {
+        let figure = *self
+            .get(from_x, from_y)
+            .ok_or(PositionError::EmptySquare(from_x, from_y))?;
+        self.set(to_x, to_y, figure)?;
+        self.clear(from_x, from_y)
     }
 
     #[inline]
     pub fn get_start_position() -> Self {
         let mut field = Self::new();
-        for i in 0..8 {
-            field.set(i, 6, Figure::new(FigureColor::White, FigureType::Pawn));
-            field.set(i, 1, Figure::new(FigureColor::Black, FigureType::Pawn));
+        let back_rank = [
+            FigureType::Rook,
+            FigureType::Knight,
+            FigureType::Bishop,
+            FigureType::Queen,
+            FigureType::King,
+            FigureType::Bishop,
+            FigureType::Knight,
+            FigureType::Rook,
+        ];
+        for (i, figure_type) in back_rank.iter().enumerate() {
+            field.figures[i] =
+                Some(Figure::new(FigureColor::Black, *figure_type));
+            field.figures[i + 8] =
+                Some(Figure::new(FigureColor::Black, FigureType::Pawn));
+            field.figures[i + 48] =
+                Some(Figure::new(FigureColor::White, FigureType::Pawn));
+            field.figures[i + 56] =
+                Some(Figure::new(FigureColor::White, *figure_type));
         }
-        field.set(0, 7, Figure::new(FigureColor::White, FigureType::Rook));
-        field.set(7, 7, Figure::new(FigureColor::White, FigureType::Rook));
-        field.set(0, 0, Figure::new(FigureColor::Black, FigureType::Rook));
-        field.set(7, 0, Figure::new(FigureColor::Black, FigureType::Rook));
-        field.set(1, 7, Figure::new(FigureColor::White, FigureType::Knight));
-        field.set(6, 7, Figure::new(FigureColor::White, FigureType::Knight));
-        field.set(1, 0, Figure::new(FigureColor::Black, FigureType::Knight));
-        field.set(6, 0, Figure::new(FigureColor::Black, FigureType::Knight));
-        field.set(2, 7, Figure::new(FigureColor::White, FigureType::Bishop));
-        field.set(5, 7, Figure::new(FigureColor::White, FigureType::Bishop));
-        field.set(2, 0, Figure::new(FigureColor::Black, FigureType::Bishop));
-        field.set(5, 0, Figure::new(FigureColor::Black, FigureType::Bishop));
-        field.set(3, 7, Figure::new(FigureColor::White, FigureType::Queen));
-        field.set(3, 0, Figure::new(FigureColor::Black, FigureType::Queen));
-        field.set(4, 7, Figure::new(FigureColor::White, FigureType::King));
-        field.set(4, 0, Figure::new(FigureColor::Black, FigureType::King));
         field
     }
 
-    pub fn is_check(&self, color: FigureColor) -> bool {
+    // parses the piece placement and side to move of a FEN string,
+    // remaining fields (castling, en passant, clocks) are ignored for now
+    pub fn from_fen(fen: &str) -> Option<(Self, FigureColor)> {
+        let mut parts = fen.split_whitespace();
+        let ranks: Vec<&str> = parts.next()?.split('/').collect();
+        if ranks.len() != 8 {
+            return None;
+        }
+
+        let mut field = Self::new();
+        for (y, rank) in ranks.iter().enumerate() {
+            let mut x = 0;
+            for c in rank.chars() {
+                if let Some(skip) = c.to_digit(10) {
+                    x += skip;
+                    continue;
+                }
+                if x > 7 {
+                    return None;
+                }
+
+                let color = if c.is_ascii_uppercase() {
+                    FigureColor::White
+                } else {
+                    FigureColor::Black
+                };
+                let figure_type = match c.to_ascii_lowercase() {
+                    'p' => FigureType::Pawn,
+                    'r' => FigureType::Rook,
+                    'n' => FigureType::Knight,
+                    'b' => FigureType::Bishop,
+                    'q' => FigureType::Queen,
+                    'k' => FigureType::King,
+                    _ => return None,
+                };
+                field
+                    .set(x, y as u32, Figure::new(color, figure_type))
+                    .ok()?;
+                x += 1;
+            }
+            if x != 8 {
+                return None;
+            }
+        }
+
+        let to_move = match parts.next() {
+            Some("w") | None => FigureColor::White,
+            Some("b") => FigureColor::Black,
+            _ => return None,
+        };
+
+        Some((field, to_move))
+    }
+
+    // the position as FEN string, `castling` in FEN order (K, Q, k, q)
+    pub fn to_fen(
+        &self,
+        to_move: FigureColor,
+        castling: [bool; 4],
+        en_passant: Option<(u32, u32)>,
+    ) -> String {
+        let mut ranks = Vec::new();
+        for y in 0..8 {
+            let mut rank = String::new();
+            let mut empty = 0;
+            for x in 0..8 {
+                let figure = match self.get(x, y) {
+                    Some(figure) => figure,
+                    None => {
+                        empty += 1;
+                        continue;
+                    }
+                };
+                if empty > 0 {
+                    rank.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                let letter = match figure.figure_type {
+                    FigureType::Pawn => 'p',
+                    FigureType::Rook => 'r',
+                    FigureType::Knight => 'n',
+                    FigureType::Bishop => 'b',
+                    FigureType::Queen => 'q',
+                    FigureType::King => 'k',
+                };
+                rank.push(match figure.color {
+                    FigureColor::White => letter.to_ascii_uppercase(),
+                    FigureColor::Black => letter,
+                });
+            }
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+            ranks.push(rank);
+        }
+
+        let side = match to_move {
+            FigureColor::White => "w",
+            FigureColor::Black => "b",
+        };
+        let en_passant =
+            en_passant.map_or("-".to_string(), |(x, y)| square_name(x, y));
+        format!(
+            "{} {} {} {} 0 1",
+            ranks.join("/"),
+            side,
+            castling_text(castling),
+            en_passant
+        )
+    }
+
+    // checks that the position can be played from, with `to_move` being the
+    // side to move next
+    pub fn validate(&self, to_move: FigureColor) -> Result<(), PositionError> {
+        for color in [FigureColor::White, FigureColor::Black] {
+            let kings = self
+                .figures
+                .iter()
+                .flatten()
+                .filter(|f| {
+                    f.color == color && f.figure_type == FigureType::King
+                })
+                .count();
+            match kings {
+                0 => return Err(PositionError::MissingKing(color)),
+                1 => {}
+                _ => return Err(PositionError::TooManyKings(color)),
+            }
+        }
+
+        for x in 0..8 {
+            for y in [0, 7] {
+                if let Some(figure) = self.get(x, y) {
+                    if figure.figure_type == FigureType::Pawn {
+                        return Err(PositionError::PawnOnBackRank(x, y));
+                    }
+                }
+            }
+        }
+
+        if self.is_check(opposite(to_move))? {
+            return Err(PositionError::OpponentInCheck);
+        }
+
+        Ok(())
+    }
+
+    pub fn find_king(&self, color: FigureColor) -> Option<(u32, u32)> {
         let king_pos = self
             .figures
             .iter()
@@ -96,15 +354,17 @@ impl Field {
                 f.as_ref().map_or(false, |f| {
                     f.color == color && f.figure_type == FigureType::King
                 })
-            })
-            .unwrap()
+            })?
             .0;
-        let king_x = king_pos as u32 % 8;
-        let king_y = king_pos as u32 / 8;
-        let enemy_color = match color {
-            FigureColor::White => FigureColor::Black,
-            FigureColor::Black => FigureColor::White,
-        };
+        Some((king_pos as u32 % 8, king_pos as u32 / 8))
+    }
+
+    // fails for positions without a king of the given color
+    pub fn is_check(&self, color: FigureColor) -> Result<bool, PositionError> {
+        let (king_x, king_y) = self
+            .find_king(color)
+            .ok_or(PositionError::MissingKing(color))?;
+        let enemy_color = opposite(color);
         for x in 0..8 {
             for y in 0..8 {
                 if let None = self.get(x, y) {
@@ -118,11 +378,11 @@ impl Field {
 
                 let moves = self.get_naive_moves(x, y);
                 if moves.contains(&(king_x, king_y)) {
-                    return true;
+                    return Ok(true);
                 }
             }
         }
-        false
+        Ok(false)
     }
     // do naive moves first
 
@@ -131,7 +391,9 @@ impl Field {
             Some(figure) => match figure.figure_type {
                 FigureType::Pawn => {
                     let mut moves = HashSet::new();
-                    if figure.color == FigureColor::White {
+                    // pawns on the last rank (only possible in hand-made
+                    // positions) have nowhere to go
+                    if figure.color == FigureColor::White && y > 0 {
                         // normal pawn movement
                         if self.get(x, y - 1).is_none() {
                             moves.insert((x, y - 1));
@@ -147,7 +409,7 @@ impl Field {
                         if x < 7 && self.get(x + 1, y - 1).is_some() {
                             moves.insert((x + 1, y - 1));
                         }
-                    } else {
+                    } else if figure.color == FigureColor::Black && y < 7 {
                         // normal pawn movement
                         if self.get(x, y + 1).is_none() {
                             moves.insert((x, y + 1));
@@ -446,11 +708,14 @@ impl Field {
 
         // filter out moves that are not possible, because of a check situation
 
+        // (without a king of this color there is no check to avoid)
         for (x_m, y_m) in naive_moves.clone() {
             let mut board = self.clone();
-            board.move_figure(x, y, x_m, y_m);
+            if board.move_figure(x, y, x_m, y_m).is_err() {
+                continue;
+            }
 
-            if !board.is_check(color) {
+            if !board.is_check(color).unwrap_or(false) {
                 moves.insert((x_m, y_m));
             }
         }
@@ -464,7 +729,7 @@ impl Field {
         let mut is_checkmate = true;
 
         for x in 0..8 {
-            for y in 0..7 {
+            for y in 0..8 {
                 if self.get(x, y).is_some()
                     && self.get(x, y).unwrap().color == color
                 {
@@ -483,7 +748,7 @@ impl Field {
         let mut is_draw = true;
 
         for x in 0..8 {
-            for y in 0..7 {
+            for y in 0..8 {
                 if self.get(x, y).is_some() {
                     if !self
                         .get_possible_moves(x, y, self.get(x, y).unwrap().color)
@@ -499,3 +764,127 @@ impl Field {
         is_draw
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_from_the_first_rank_are_not_checkmate() {
+        // the white king on a1 is the only white figure
+        let (field, _) = Field::from_fen("7k/8/8/8/8/8/8/K7 w").unwrap();
+        assert!(!field.is_checkmate(FigureColor::White));
+        assert!(!field.is_draw());
+    }
+
+    #[test]
+    fn kingless_positions_are_not_checked() {
+        let mut field = Field::new();
+        let rook = Figure::new(FigureColor::Black, FigureType::Rook);
+        field.set(0, 0, rook).unwrap();
+        assert_eq!(
+            field.is_check(FigureColor::White),
+            Err(PositionError::MissingKing(FigureColor::White))
+        );
+        // no king to protect, the rook moves freely
+        assert_eq!(
+            field.get_possible_moves(0, 0, FigureColor::Black).len(),
+            14
+        );
+    }
+
+    #[test]
+    fn squares_off_the_board() {
+        let mut field = Field::get_start_position();
+        let pawn = Figure::new(FigureColor::White, FigureType::Pawn);
+        assert_eq!(field.get(8, 0), None);
+        assert_eq!(field.get(0, 8), None);
+        assert_eq!(field.get(u32::MAX, u32::MAX), None);
+        assert!(field
+            .get_possible_moves(u32::MAX, 0, FigureColor::White)
+            .is_empty());
+        assert_eq!(field.set(8, 0, pawn), Err(PositionError::OffBoard(8, 0)));
+        assert_eq!(
+            field.clear(0, u32::MAX),
+            Err(PositionError::OffBoard(0, u32::MAX))
+        );
+        assert_eq!(
+            field.move_figure(4, 6, 4, 8),
+            Err(PositionError::OffBoard(4, 8))
+        );
+        assert_eq!(
+            field.move_figure(4, 4, 4, 3),
+            Err(PositionError::EmptySquare(4, 4))
+        );
+    }
+
+    #[test]
+    fn pawns_on_the_last_rank_have_no_moves() {
+        let mut field = Field::new();
+        let white = Figure::new(FigureColor::White, FigureType::Pawn);
+        let black = Figure::new(FigureColor::Black, FigureType::Pawn);
+        field.set(3, 0, white).unwrap();
+        field.set(3, 7, black).unwrap();
+        assert!(field
+            .get_possible_moves(3, 0, FigureColor::White)
+            .is_empty());
+        assert!(field
+            .get_possible_moves(3, 7, FigureColor::Black)
+            .is_empty());
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR b - - 0 1";
+        let (field, to_move) = Field::from_fen(fen).unwrap();
+        assert_eq!(field.to_fen(to_move, [false; 4], None), fen);
+
+        let (field, to_move) =
+            Field::from_fen("rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b")
+                .unwrap();
+        assert_eq!(
+            field.to_fen(to_move, [true, false, true, true], Some((3, 5))),
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kkq d3 0 1"
+        );
+    }
+
+    #[test]
+    fn malformed_fen() {
+        // seven ranks
+        assert_eq!(Field::from_fen("8/8/8/8/8/8/8 w"), None);
+        // nine squares in a rank
+        assert_eq!(Field::from_fen("9/8/8/8/8/8/8/8 w"), None);
+        assert_eq!(Field::from_fen("ppppppppp/8/8/8/8/8/8/8 w"), None);
+        // six squares in a rank
+        assert_eq!(Field::from_fen("6/8/8/8/8/8/8/8 w"), None);
+        assert_eq!(Field::from_fen("4x3/8/8/8/8/8/8/8 w"), None);
+        assert_eq!(Field::from_fen("8/8/8/8/8/8/8/8 x"), None);
+        assert_eq!(Field::from_fen(""), None);
+    }
+
+    #[test]
+    fn invalid_positions() {
+        let validate = |fen: &str| {
+            let (field, to_move) = Field::from_fen(fen).unwrap();
+            field.validate(to_move)
+        };
+        assert_eq!(validate("4k3/8/8/8/8/8/8/4K3 w"), Ok(()));
+        assert_eq!(
+            validate("8/8/8/8/8/8/8/4K3 w"),
+            Err(PositionError::MissingKing(FigureColor::Black))
+        );
+        assert_eq!(
+            validate("4k3/8/8/8/8/8/8/K3K3 w"),
+            Err(PositionError::TooManyKings(FigureColor::White))
+        );
+        assert_eq!(
+            validate("P3k3/8/8/8/8/8/8/4K3 w"),
+            Err(PositionError::PawnOnBackRank(0, 0))
+        );
+        // white to move but black is in check
+        assert_eq!(
+            validate("4k3/8/8/8/8/8/8/4R1K1 w"),
+            Err(PositionError::OpponentInCheck)
+        );
+    }
+}
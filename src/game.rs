@@ -0,0 +1,156 @@
+use crate::field::{opposite, Field, Figure, FigureColor, PositionError};
+use crate::notation::{self, Move};
+
+// how a game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    // with the winner
+    Checkmate(FigureColor),
+    // the side to move has no legal move but is not in check
+    Stalemate,
+    // neither side has a legal move
+    Draw,
+}
+
+impl Outcome {
+    // the result as written in pgn, e.g. "1-0"
+    pub fn result(self) -> &'static str {
+        match self {
+            Outcome::Checkmate(FigureColor::White) => "1-0",
+            Outcome::Checkmate(FigureColor::Black) => "0-1",
+            Outcome::Stalemate | Outcome::Draw => "1/2-1/2",
+        }
+    }
+}
+
+// the outcome of the position with `to_move` to move next, None while the
+// game goes on
+pub fn outcome(field: &Field, to_move: FigureColor) -> Option<Outcome> {
+    // is_checkmate only says that there is no legal move left
+    if field.is_checkmate(to_move) {
+        if field.is_check(to_move) == Ok(true) {
+            Some(Outcome::Checkmate(opposite(to_move)))
+        } else {
+            Some(Outcome::Stalemate)
+        }
+    } else if field.is_draw() {
+        Some(Outcome::Draw)
+    } else {
+        None
+    }
+}
+
+// a game from some starting position, shared by the front ends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub field: Field,
+    pub to_move: FigureColor,
+    pub captured_white: Vec<Figure>,
+    pub captured_black: Vec<Figure>,
+    pub last_move: Option<Move>,
+    // the position the game started from and the side to move in it
+    pub start: (Field, FigureColor),
+    // castling rights (K, Q, k, q) and en passant square of `start`, as set
+    // up in the editor, the rules don't play them yet
+    pub start_castling: [bool; 4],
+    pub start_en_passant: Option<(u32, u32)>,
+    // the moves played since `start`
+    pub history: Vec<Move>,
+    pub outcome: Option<Outcome>,
+}
+
+impl Game {
+    pub fn new(field: Field, to_move: FigureColor) -> Self {
+        Self {
+            outcome: outcome(&field, to_move),
+            start: (field.clone(), to_move),
+            start_castling: [false; 4],
+            start_en_passant: None,
+            field,
+            to_move,
+            captured_white: Vec::new(),
+            captured_black: Vec::new(),
+            last_move: None,
+            history: Vec::new(),
+        }
+    }
+
+    // moves a figure and passes the turn to the other player, returns the
+    // captured figure
+    pub fn play(&mut self, mv: Move) -> Result<Option<Figure>, PositionError> {
+        let ((from_x, from_y), (to_x, to_y)) = mv;
+        let captured = self.field.get(to_x, to_y).copied();
+        self.field.move_figure(from_x, from_y, to_x, to_y)?;
+
+        if let Some(figure) = captured {
+            if figure.color == FigureColor::White {
+                self.captured_white.push(figure);
+            } else {
+                self.captured_black.push(figure);
+            }
+        }
+        self.to_move = opposite(self.to_move);
+        self.last_move = Some(mv);
+        self.history.push(mv);
+        self.outcome = outcome(&self.field, self.to_move);
+        Ok(captured)
+    }
+
+    // the start position as FEN string
+    pub fn start_fen(&self) -> String {
+        let (field, to_move) = &self.start;
+        field.to_fen(*to_move, self.start_castling, self.start_en_passant)
+    }
+
+    // the moves of the history in san
+    pub fn san_history(&self) -> Vec<String> {
+        let mut replay = Game::new(self.start.0.clone(), self.start.1);
+        let mut moves = Vec::new();
+        for &mv in &self.history {
+            moves.push(notation::san(&replay.field, mv));
+            if replay.play(mv).is_err() {
+                break;
+            }
+        }
+        moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(fen: &str) -> (Field, FigureColor) {
+        Field::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn outcomes() {
+        let (field, to_move) = position("6k1/6Q1/6K1/8/8/8/8/8 b");
+        assert_eq!(
+            outcome(&field, to_move),
+            Some(Outcome::Checkmate(FigureColor::White))
+        );
+        let (field, to_move) = position("7k/5Q2/6K1/8/8/8/8/8 b");
+        assert_eq!(outcome(&field, to_move), Some(Outcome::Stalemate));
+        let (field, to_move) = position("6k1/5Q2/6K1/8/8/8/8/8 b");
+        assert_eq!(outcome(&field, to_move), None);
+    }
+
+    #[test]
+    fn playing_a_game() {
+        let mut game =
+            Game::new(Field::get_start_position(), FigureColor::White);
+        for mv in ["f2f3", "e7e5", "g2g4"] {
+            game.play(notation::parse_move(mv).unwrap()).unwrap();
+            assert_eq!(game.outcome, None);
+        }
+        game.play(notation::parse_move("d8h4").unwrap()).unwrap();
+        assert_eq!(game.outcome, Some(Outcome::Checkmate(FigureColor::Black)));
+        assert_eq!(game.san_history(), ["f3", "e5", "g4", "Qh4#"]);
+        assert_eq!(game.to_move, FigureColor::White);
+
+        // no figure to move
+        assert!(game.play(notation::parse_move("e4e5").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+// the rules, game state and move notation, shared by the sdl client, the
+// terminal front end and the fuzz target
+pub mod field;
+pub mod game;
+pub mod notation;
+pub mod puzzle;
@@ -1,26 +1,51 @@
 extern crate sdl2;
 
-mod field;
+mod annotation;
+mod assets;
+mod editor;
+mod pgn;
+mod sound;
+mod svg;
+mod theme;
+
+use rusty_chess::{field, game, notation, puzzle};
 
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
+use sdl2::keyboard::Mod;
 use sdl2::libc::SYS_process_vm_writev;
 use sdl2::mouse::MouseButton;
 use sdl2::pixels::Color;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Point;
 use sdl2::rect::Rect;
+use sdl2::render::BlendMode;
+use sdl2::render::Canvas;
+use sdl2::render::RenderTarget;
 use sdl2::render::WindowCanvas;
+use sdl2::rwops::RWops;
+use sdl2::surface::Surface;
 
 use sdl2::image::LoadTexture;
+use sdl2::image::SaveSurface;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::f32::consts::TAU;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
 use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
 
-static BG_COLOR: Color = Color::RGB(50, 50, 50);
-static AUX_COLOR: Color = Color::RGB(100, 100, 100);
-static FG_COLOR: Color = Color::RGB(170, 170, 170);
-static HIGHLIGHT_COLOR: Color = Color::RGB(255, 92, 51);
 static WIDTH: u32 = 1200;
+static FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+static ANIMATION_DURATION: Duration = Duration::from_millis(200);
+static VOLUME: u8 = 80;
+static GIF_SIZE: u32 = 480;
+static GIF_DELAY: Duration = Duration::from_millis(1000);
 static HEIGHT: u32 = 900;
 
 // minor ui constants
@@ -29,28 +54,378 @@ static PADDING: u32 = 5;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// a figure sliding from one square to another, fading out what it captured
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Animation {
+    figure: field::Figure,
+    from: (u32, u32),
+    to: (u32, u32),
+    captured: Option<field::Figure>,
+    started: Instant,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct GameState {
-    field: field::Field,
-    whites_turn: bool,
-    captured_white: Vec<field::Figure>,
-    captured_black: Vec<field::Figure>,
+    game: game::Game,
     marked: Option<(u32, u32)>,
     possible_moves: HashSet<(u32, u32)>,
-    checkmate: bool,
-    draw: bool,
+    // arrows and circles, keyed by the number of moves played before the
+    // position they were drawn on
+    annotations: HashMap<usize, Vec<annotation::Annotation>>,
+    animation: Option<Animation>,
+    // zero turns animations off
+    animation_duration: Duration,
+    puzzle: Option<puzzle::PuzzleSession>,
+    editor: Option<editor::Editor>,
+    // the game to go back to when the editor is left with Escape
+    before_editor: Option<Box<GameState>>,
+    // cursor of the open settings menu
+    settings: Option<usize>,
+    // black at the bottom of the board
+    flipped: bool,
+    // sound of the last move, played by the main loop
+    sound: Option<sound::Sound>,
+    // in percent
+    volume: u8,
+    muted: bool,
+    // the move being typed, None while the input line is closed
+    input: Option<String>,
+    // the legal moves matching `input`, and the text, position and side to
+    // move they were computed for, see update_suggestions
+    suggestions: Vec<(notation::Move, String)>,
+    suggested_for: Option<(String, field::Field, field::FigureColor)>,
+    // square picked with the arrow keys
+    cursor: Option<(u32, u32)>,
 }
 
-fn render_field(
-    canvas: &mut WindowCanvas,
+impl GameState {
+    fn new(field: field::Field, to_move: field::FigureColor) -> Self {
+        Self {
+            game: game::Game::new(field, to_move),
+            marked: None,
+            possible_moves: HashSet::new(),
+            annotations: HashMap::new(),
+            animation: None,
+            animation_duration: ANIMATION_DURATION,
+            puzzle: None,
+            editor: None,
+            before_editor: None,
+            settings: None,
+            flipped: false,
+            sound: None,
+            volume: VOLUME,
+            muted: false,
+            input: None,
+            suggestions: Vec::new(),
+            suggested_for: None,
+            cursor: None,
+        }
+    }
+}
+
+// moves a figure and passes the turn to the other player
+fn make_move(state: &mut GameState, from: (u32, u32), to: (u32, u32)) {
+    let moving = state.game.field.get(from.0, from.1).copied();
+    let captured = match state.game.play((from, to)) {
+        Ok(captured) => captured,
+        Err(_) => return,
+    };
+
+    state.marked = None;
+    state.possible_moves.clear();
+
+    if let Some(figure) = moving {
+        if !state.animation_duration.is_zero() {
+            state.animation = Some(Animation {
+                figure,
+                from,
+                to,
+                captured,
+                started: Instant::now(),
+            });
+        }
+    }
+
+    let color = players_color(state);
+    state.sound = Some(if state.game.outcome.is_some() {
+        sound::Sound::GameOver
+    } else if state.game.field.is_check(color) == Ok(true) {
+        sound::Sound::Check
+    } else if captured.is_some() {
+        sound::Sound::Capture
+    } else {
+        sound::Sound::Move
+    });
+}
+
+// sets up the board for the current puzzle and plays the opponent's first move
+fn start_puzzle(state: &mut GameState) {
+    let session = match state.puzzle.as_mut() {
+        Some(session) => session,
+        None => return,
+    };
+    let puzzle = session.puzzle().clone();
+
+    state.game = game::Game::new(puzzle.field, puzzle.to_move);
+    state.marked = None;
+    state.possible_moves.clear();
+    state.annotations.clear();
+
+    play_puzzle_reply(state);
+}
+
+fn open_editor(state: &mut GameState) {
+    state.before_editor = Some(Box::new(state.clone()));
+    state.editor = Some(editor::Editor::new());
+    state.game = game::Game::new(state.game.field.clone(), state.game.to_move);
+    state.marked = None;
+    state.possible_moves.clear();
+}
+
+fn handle_editor_key(state: &mut GameState, keycode: Keycode) {
+    if keycode == Keycode::Escape {
+        if let Some(previous) = state.before_editor.take() {
+            *state = *previous;
+        }
+        return;
+    }
+
+    let to_move = players_color(state);
+    let editor = match state.editor.as_mut() {
+        Some(editor) => editor,
+        None => return,
+    };
+
+    match keycode {
+        Keycode::Tab => {
+            state.game.to_move = field::opposite(state.game.to_move);
+            editor.error = None;
+        }
+        Keycode::Num1 => editor.toggle_castling(0),
+        Keycode::Num2 => editor.toggle_castling(1),
+        Keycode::Num3 => editor.toggle_castling(2),
+        Keycode::Num4 => editor.toggle_castling(3),
+        Keycode::C => state.game.field = field::Field::new(),
+        Keycode::S => state.game.field = field::Field::get_start_position(),
+        Keycode::Return => {
+            // only leave the editor for positions that can be played
+            match editor.validate(&state.game.field, to_move) {
+                Ok(()) => {
+                    let mut game =
+                        game::Game::new(state.game.field.clone(), to_move);
+                    game.start_castling = editor.castling;
+                    game.start_en_passant = editor.en_passant;
+                    state.game = game;
+                    state.editor = None;
+                    state.before_editor = None;
+                    state.annotations.clear();
+                }
+                Err(e) => editor.error = Some(e),
+            }
+        }
+        _ => {}
+    }
+}
+
+// moves the cursor of the settings menu and sets the volume, returns the
+// theme to switch to
+fn handle_settings_key(
+    state: &mut GameState,
+    keycode: Keycode,
+    theme_count: usize,
+) -> Option<usize> {
+    let cursor = state.settings.as_mut()?;
+    match keycode {
+        Keycode::Up => *cursor = (*cursor + theme_count - 1) % theme_count,
+        Keycode::Down => *cursor = (*cursor + 1) % theme_count,
+        Keycode::Return => return Some(*cursor),
+        Keycode::Left => state.volume = state.volume.saturating_sub(10),
+        Keycode::Right => state.volume = (state.volume + 10).min(100),
+        Keycode::M => state.muted = !state.muted,
+        Keycode::Escape | Keycode::O => state.settings = None,
+        _ => {}
+    }
+    None
+}
+
+fn play_puzzle_reply(state: &mut GameState) {
+    let reply = state.puzzle.as_mut().and_then(|s| s.opponent_reply());
+    if let Some((from, to)) = reply {
+        make_move(state, from, to);
+    }
+}
+
+fn players_color(state: &GameState) -> field::FigureColor {
+    state.game.to_move
+}
+
+// no more moves once the game or the puzzle is over
+fn can_move(state: &GameState) -> bool {
+    state.game.outcome.is_none()
+        && !state.puzzle.as_ref().is_some_and(|s| s.is_solved())
+}
+
+// plays a move of the player, in puzzle mode only moves of the solution are
+// accepted
+fn play_move(state: &mut GameState, from: (u32, u32), to: (u32, u32)) {
+    let accepted = match state.puzzle.as_mut() {
+        Some(session) => session.check_move(&state.game.field, (from, to)),
+        None => true,
+    };
+
+    if accepted {
+        make_move(state, from, to);
+        play_puzzle_reply(state);
+    } else {
+        state.possible_moves.clear();
+    }
+    state.marked = None;
+}
+
+// marks a figure of the player and shows its moves, or moves the marked
+// figure there, for clicks and the keyboard cursor alike
+fn select_square(state: &mut GameState, field_x: u32, field_y: u32) {
+    if !can_move(state) {
+        return;
+    }
+
+    let mut marked = Some((field_x, field_y));
+    let players_color = players_color(state);
+
+    // if there is a figure on the clicked field, show possible moves
+    if let Some(figure) = state.game.field.get(field_x, field_y) {
+        // if the clicked figure is not the current player's, ignore
+        if figure.color == players_color {
+            state.possible_moves = state.game.field.get_possible_moves(
+                field_x,
+                field_y,
+                players_color,
+            );
+        } else if !state.possible_moves.contains(&(field_x, field_y)) {
+            marked = None;
+            state.possible_moves.clear();
+        }
+    }
+    if let Some(from) = state.marked {
+        if state.possible_moves.contains(&(field_x, field_y)) {
+            play_move(state, from, (field_x, field_y));
+            return;
+        }
+    }
+
+    state.marked = marked;
+}
+
+// moves the square cursor, starting on the king's file of the player
+fn move_cursor(state: &mut GameState, dx: i32, dy: i32) {
+    let (x, y) = match state.cursor {
+        Some((x, y)) => (
+            (x as i32 + dx).clamp(0, 7) as u32,
+            (y as i32 + dy).clamp(0, 7) as u32,
+        ),
+        None if state.game.to_move == field::FigureColor::White => (4, 6),
+        None => (4, 1),
+    };
+    state.cursor = Some((x, y));
+}
+
+// finding the suggestions names every legal move in san, so they are only
+// looked up again once the typed text or the position changed
+fn update_suggestions(state: &mut GameState) {
+    let text = match &state.input {
+        Some(text) => text,
+        None => {
+            state.suggestions.clear();
+            state.suggested_for = None;
+            return;
+        }
+    };
+    let key = (text.clone(), state.game.field.clone(), state.game.to_move);
+    if state.suggested_for.as_ref() != Some(&key) {
+        state.suggestions = notation::suggestions(
+            &state.game.field,
+            players_color(state),
+            text,
+        );
+        state.suggested_for = Some(key);
+    }
+}
+
+// keys while typing a move: Return plays it, Tab completes the first
+// suggestion and Escape closes the input line
+fn handle_input_key(state: &mut GameState, keycode: Keycode) {
+    let color = players_color(state);
+    // text typed since the last frame
+    update_suggestions(state);
+    let text = match state.input.as_mut() {
+        Some(text) => text,
+        None => return,
+    };
+
+    match keycode {
+        Keycode::Backspace => {
+            text.pop();
+        }
+        Keycode::Tab => {
+            if let Some((_, san)) = state.suggestions.first() {
+                *text = san.clone();
+            }
+        }
+        Keycode::Return => {
+            let mv = notation::parse(&state.game.field, color, text.as_str());
+            if let Some((from, to)) = mv.filter(|_| can_move(state)) {
+                state.input = Some(String::new());
+                play_move(state, from, to);
+            }
+        }
+        Keycode::Escape => state.input = None,
+        _ => {}
+    }
+}
+
+// draws the outline of a rect with the given line width, using the current
+// draw color
+fn draw_frame<T: RenderTarget>(canvas: &mut Canvas<T>, rect: Rect, width: u32) {
+    // draw rect with width using 4 rects
+    let upper = Rect::new(rect.x(), rect.y(), rect.width(), width);
+    let lower = Rect::new(
+        rect.x(),
+        rect.y() + rect.height() as i32 - width as i32,
+        rect.width(),
+        width,
+    );
+    let left = Rect::new(rect.x(), rect.y(), width, rect.height());
+    let right = Rect::new(
+        rect.x() + rect.width() as i32 - width as i32,
+        rect.y(),
+        width,
+        rect.height(),
+    );
+    canvas.fill_rect(upper).unwrap();
+    canvas.fill_rect(lower).unwrap();
+    canvas.fill_rect(left).unwrap();
+    canvas.fill_rect(right).unwrap();
+}
+
+// draws the board into any canvas, the window or an offscreen surface
+// inset of pieces and marks from the edge of a square, shrinking with the
+// square so that the glow of a checked king (nine times the padding) and
+// the pieces still fit into small images
+fn square_padding(square_size: u32) -> u32 {
+    PADDING.min(square_size / 16)
+}
+
+fn render_field<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    theme: &theme::Theme,
     bounds: Rect,
     state: &GameState,
-    textures: &HashMap<field::Figure, sdl2::render::Texture>,
+    textures: &mut HashMap<field::Figure, sdl2::render::Texture>,
     font: &sdl2::ttf::Font,
-    texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+    texture_creator: &sdl2::render::TextureCreator<T::Context>,
 ) {
     // draw field border
-    canvas.set_draw_color(AUX_COLOR);
+    canvas.set_draw_color(theme.aux);
     canvas.fill_rect(bounds).unwrap();
 
     // update bounds of field to account border
@@ -61,24 +436,54 @@ fn render_field(
         bounds.height() - (BORDER_WIDTH * 2) as u32,
     );
     // draw field background
-    canvas.set_draw_color(BG_COLOR);
+    canvas.set_draw_color(theme.background);
     canvas.fill_rect(bounds).unwrap();
 
     let square_size: u32 = bounds.width() / 8;
+    let padding = square_padding(square_size);
+
+    // screen column and row of a square, with black at the bottom when the
+    // board is flipped
+    let flip = |(x, y): (u32, u32)| {
+        if state.flipped {
+            (7 - x, 7 - y)
+        } else {
+            (x, y)
+        }
+    };
+
+    // how far the current animation has come, from 0 to 1
+    let animation = state.animation.as_ref().map(|animation| {
+        let progress = animation.started.elapsed().as_secs_f32()
+            / state.animation_duration.as_secs_f32();
+        (animation, progress.min(1.0))
+    });
+
+    // the king of the side to move, if it is in check
+    let to_move = players_color(state);
+    let checked_king = match state.game.field.is_check(to_move) {
+        Ok(true) => state.game.field.find_king(to_move),
+        _ => None,
+    };
 
     for x in 0..8 {
         for y in 0..8 {
-            let color = if (x + y) % 2 == 0 { FG_COLOR } else { BG_COLOR };
+            let color = if (x + y) % 2 == 0 {
+                theme.light_square
+            } else {
+                theme.dark_square
+            };
 
+            let (column, row) = flip((x, y));
             let mut square = Rect::new(
-                bounds.x() + (x * square_size) as i32,
-                bounds.y() + (y * square_size) as i32,
+                bounds.x() + (column * square_size) as i32,
+                bounds.y() + (row * square_size) as i32,
                 square_size,
                 square_size,
             );
 
             // account for uneven division of field size by 8
-            if x == 7 {
+            if column == 7 {
                 if square.x() + square.width() as i32
                     != bounds.x() + bounds.width() as i32
                 {
@@ -88,7 +493,7 @@ fn render_field(
                 }
             }
 
-            if y == 7 {
+            if row == 7 {
                 if square.y() + square.height() as i32
                     != bounds.y() + bounds.height() as i32
                 {
@@ -101,14 +506,40 @@ fn render_field(
             canvas.set_draw_color(color);
             canvas.fill_rect(square).unwrap();
 
+            // tint origin and destination of the last move
+            if let Some((from, to)) = state.game.last_move {
+                if (x, y) == from || (x, y) == to {
+                    canvas.set_blend_mode(BlendMode::Blend);
+                    canvas.set_draw_color(theme.last_move);
+                    canvas.fill_rect(square).unwrap();
+                    canvas.set_blend_mode(BlendMode::None);
+                }
+            }
+
+            // red glow around a king in check, stacking translucent rects
+            // so it gets stronger towards the center
+            if Some((x, y)) == checked_king {
+                canvas.set_blend_mode(BlendMode::Blend);
+                canvas.set_draw_color(theme.check);
+                for i in 0..4 {
+                    let glow = Rect::from_center(
+                        square.center(),
+                        square.width() - i * padding * 3,
+                        square.height() - i * padding * 3,
+                    );
+                    canvas.fill_rect(glow).unwrap();
+                }
+                canvas.set_blend_mode(BlendMode::None);
+            }
+
             // for edges draw letters and numbers
-            if x == 0 {
+            if column == 0 {
                 let text = font
                     .render(&format!("{}", 8 - y))
-                    .blended(if color == FG_COLOR {
-                        BG_COLOR
+                    .blended(if color == theme.light_square {
+                        theme.dark_square
                     } else {
-                        FG_COLOR
+                        theme.light_square
                     })
                     .unwrap();
                 let texture =
@@ -117,8 +548,8 @@ fn render_field(
                 let dimensions = texture.query();
                 // offset so that number is always at the top left corner of the square
                 let target = Rect::new(
-                    square.x() + PADDING as i32,
-                    square.y() + PADDING as i32,
+                    square.x() + padding as i32,
+                    square.y() + padding as i32,
                     dimensions.width,
                     dimensions.height,
                 );
@@ -127,13 +558,13 @@ fn render_field(
             }
 
             // Render Column Letters
-            if y == 7 {
+            if row == 7 {
                 let text = font
                     .render(&format!("{}", (x as u8 + b'a') as char))
-                    .blended(if color == FG_COLOR {
-                        BG_COLOR
+                    .blended(if color == theme.light_square {
+                        theme.dark_square
                     } else {
-                        FG_COLOR
+                        theme.light_square
                     })
                     .unwrap();
                 let texture =
@@ -144,10 +575,10 @@ fn render_field(
                 let target = Rect::new(
                     square.x() - dimensions.width as i32
                         + square.width() as i32
-                        - PADDING as i32,
+                        - padding as i32,
                     square.y() - dimensions.height as i32
                         + square.height() as i32
-                        - PADDING as i32,
+                        - padding as i32,
                     dimensions.width,
                     dimensions.height,
                 );
@@ -156,112 +587,266 @@ fn render_field(
             }
 
             // draw piece
-            if let Some(figure) = state.field.get(x, y) {
-                let sprite = textures.get(figure).unwrap();
-
+            if let Some(figure) = state.game.field.get(x, y) {
                 // offset  figure a bit from the square
                 let target = Rect::new(
-                    square.x() + PADDING as i32,
-                    square.y() + PADDING as i32,
-                    square.width() - (PADDING * 2) as u32,
-                    square.height() - (PADDING * 2) as u32,
+                    square.x() + padding as i32,
+                    square.y() + padding as i32,
+                    square.width() - padding * 2,
+                    square.height() - padding * 2,
                 );
-                canvas.copy(sprite, None, target).unwrap();
+
+                match animation {
+                    // the moving figure is drawn after all squares, only the
+                    // captured one fades out here
+                    Some((animation, progress)) if animation.to == (x, y) => {
+                        if let Some(captured) = animation.captured {
+                            let sprite = textures.get_mut(&captured).unwrap();
+                            sprite.set_alpha_mod(
+                                ((1.0 - progress) * 255.0) as u8,
+                            );
+                            canvas.copy(sprite, None, target).unwrap();
+                            sprite.set_alpha_mod(255);
+                        }
+                    }
+                    _ => {
+                        let sprite = textures.get(figure).unwrap();
+                        canvas.copy(sprite, None, target).unwrap();
+                    }
+                }
 
                 // draw mark if square is marked
                 if Some((x, y)) == state.marked {
-                    canvas.set_draw_color(HIGHLIGHT_COLOR);
+                    canvas.set_draw_color(theme.highlight);
                     // inset mark a bit
                     let mark = Rect::new(
-                        square.x() + PADDING as i32,
-                        square.y() + PADDING as i32,
-                        square.width() - (PADDING * 2) as u32,
-                        square.height() - (PADDING * 2) as u32,
-                    );
-
-                    // draw rect with width using 4 rects
-                    let upper =
-                        Rect::new(mark.x(), mark.y(), mark.width(), PADDING);
-                    let lower = Rect::new(
-                        mark.x(),
-                        mark.y() + mark.height() as i32 - PADDING as i32,
-                        mark.width(),
-                        PADDING,
-                    );
-                    let left =
-                        Rect::new(mark.x(), mark.y(), PADDING, mark.height());
-                    let right = Rect::new(
-                        mark.x() + mark.width() as i32 - PADDING as i32,
-                        mark.y(),
-                        PADDING,
-                        mark.height(),
+                        square.x() + padding as i32,
+                        square.y() + padding as i32,
+                        square.width() - padding * 2,
+                        square.height() - padding * 2,
                     );
-                    canvas.fill_rect(upper).unwrap();
-                    canvas.fill_rect(lower).unwrap();
-                    canvas.fill_rect(left).unwrap();
-                    canvas.fill_rect(right).unwrap();
+                    draw_frame(canvas, mark, padding);
                 }
             }
             // check if square is in possible_moves
             if state.possible_moves.contains(&(x, y)) {
-                canvas.set_draw_color(AUX_COLOR);
+                canvas.set_draw_color(theme.aux);
                 // inset mark a bit
                 let mark = Rect::new(
-                    square.x() + PADDING as i32,
-                    square.y() + PADDING as i32,
-                    square.width() - (PADDING * 2) as u32,
-                    square.height() - (PADDING * 2) as u32,
+                    square.x() + padding as i32,
+                    square.y() + padding as i32,
+                    square.width() - padding * 2,
+                    square.height() - padding * 2,
                 );
 
-                if let Some(..) = state.field.get(x, y) {
-                    // draw rect with width using 4 rects
-                    let upper =
-                        Rect::new(mark.x(), mark.y(), mark.width(), PADDING);
-                    let lower = Rect::new(
-                        mark.x(),
-                        mark.y() + mark.height() as i32 - PADDING as i32,
-                        mark.width(),
-                        PADDING,
-                    );
-                    let left =
-                        Rect::new(mark.x(), mark.y(), PADDING, mark.height());
-                    let right = Rect::new(
-                        mark.x() + mark.width() as i32 - PADDING as i32,
-                        mark.y(),
-                        PADDING,
-                        mark.height(),
-                    );
-                    canvas.fill_rect(upper).unwrap();
-                    canvas.fill_rect(lower).unwrap();
-                    canvas.fill_rect(left).unwrap();
-                    canvas.fill_rect(right).unwrap();
+                if let Some(..) = state.game.field.get(x, y) {
+                    draw_frame(canvas, mark, padding);
                 } else {
                     // for empty field draw a small rect
                     let mark = Rect::from_center(
                         mark.center(),
-                        3 * PADDING as u32,
-                        3 * PADDING as u32,
+                        3 * padding,
+                        3 * padding,
                     );
                     canvas.fill_rect(mark).unwrap();
                 }
             }
+
+            // keyboard cursor around the whole square
+            if state.cursor == Some((x, y)) {
+                canvas.set_draw_color(theme.highlight);
+                draw_frame(canvas, square, BORDER_WIDTH as u32 * 2);
+            }
+        }
+    }
+
+    if let Some((animation, progress)) = animation {
+        // ease in and out of the move
+        let t = progress * progress * (3.0 - 2.0 * progress);
+        let position = |from: u32, to: u32| {
+            let from = (from * square_size) as f32;
+            let to = (to * square_size) as f32;
+            (from + (to - from) * t) as i32
+        };
+
+        let (from, to) = (flip(animation.from), flip(animation.to));
+        let target = Rect::new(
+            bounds.x() + position(from.0, to.0) + padding as i32,
+            bounds.y() + position(from.1, to.1) + padding as i32,
+            square_size - padding * 2,
+            square_size - padding * 2,
+        );
+        let sprite = textures.get(&animation.figure).unwrap();
+        canvas.copy(sprite, None, target).unwrap();
+    }
+
+    // annotations are drawn over the pieces
+    if let Some(annotations) = state.annotations.get(&state.game.history.len())
+    {
+        render_annotations(canvas, bounds, annotations, state.flipped);
+    }
+}
+
+fn annotation_color(color: annotation::AnnotationColor) -> Color {
+    let (r, g, b) = color.rgb();
+    Color::RGB(r, g, b)
+}
+
+// draws arrows and circled squares, `bounds` being the area of the squares
+fn render_annotations<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    bounds: Rect,
+    annotations: &[annotation::Annotation],
+    flipped: bool,
+) {
+    let square_size = bounds.width() as f32 / 8.0;
+    let center = |(x, y): (u32, u32)| {
+        let (x, y) = if flipped { (7 - x, 7 - y) } else { (x, y) };
+        (
+            bounds.x() as f32 + (x as f32 + 0.5) * square_size,
+            bounds.y() as f32 + (y as f32 + 0.5) * square_size,
+        )
+    };
+    let point =
+        |(x, y): (f32, f32)| Point::new(x.round() as i32, y.round() as i32);
+
+    // sdl has no thick primitives, so shapes are filled with lines half a
+    // pixel apart
+    for annotation in annotations {
+        canvas.set_draw_color(annotation_color(annotation.color()));
+        match *annotation {
+            annotation::Annotation::Arrow { from, to, .. } => {
+                let start = center(from);
+                let tip = center(to);
+                let (dx, dy) = (tip.0 - start.0, tip.1 - start.1);
+                let length = (dx * dx + dy * dy).sqrt();
+
+                // unit vectors along and across the arrow
+                let (ux, uy) = (dx / length, dy / length);
+                let (nx, ny) = (-uy, ux);
+
+                let head_length = square_size * 0.4;
+                let head_base =
+                    (tip.0 - ux * head_length, tip.1 - uy * head_length);
+
+                let shaft_width = square_size * 0.12;
+                let mut offset = -shaft_width / 2.0;
+                while offset <= shaft_width / 2.0 {
+                    canvas
+                        .draw_line(
+                            point((
+                                start.0 + nx * offset,
+                                start.1 + ny * offset,
+                            )),
+                            point((
+                                head_base.0 + nx * offset,
+                                head_base.1 + ny * offset,
+                            )),
+                        )
+                        .unwrap();
+                    offset += 0.5;
+                }
+
+                // the head is a fan of lines from the tip to its base
+                let head_width = square_size * 0.4;
+                let mut offset = -head_width / 2.0;
+                while offset <= head_width / 2.0 {
+                    canvas
+                        .draw_line(
+                            point(tip),
+                            point((
+                                head_base.0 + nx * offset,
+                                head_base.1 + ny * offset,
+                            )),
+                        )
+                        .unwrap();
+                    offset += 0.5;
+                }
+            }
+            annotation::Annotation::Circle { square, .. } => {
+                let (cx, cy) = center(square);
+                let outer = square_size * 0.47;
+                let mut radius = outer - square_size * 0.08;
+                while radius <= outer {
+                    let points: Vec<Point> = (0..=64)
+                        .map(|i| {
+                            let angle = i as f32 / 64.0 * TAU;
+                            point((
+                                cx + radius * angle.cos(),
+                                cy + radius * angle.sin(),
+                            ))
+                        })
+                        .collect();
+                    canvas.draw_lines(points.as_slice()).unwrap();
+                    radius += 0.5;
+                }
+            }
         }
     }
 }
 
+// the board square under a window position, if any
+fn board_square(x: i32, y: i32) -> Option<(u32, u32)> {
+    if x < 0 || y < 0 || x as u32 >= HEIGHT || y as u32 >= HEIGHT {
+        return None;
+    }
+    let square_size = HEIGHT / 8;
+    Some((
+        (x as u32 / square_size).min(7),
+        (y as u32 / square_size).min(7),
+    ))
+}
+
+// position of a palette entry of the editor, laid out in the middle part
+// of the sidebar in rows of six
+fn palette_slot(sidebar: Rect, index: usize) -> Rect {
+    let size = (sidebar.width() - PADDING * 2) / 6;
+    Rect::new(
+        sidebar.x() + PADDING as i32 + (index % 6) as i32 * size as i32,
+        sidebar.y()
+            + (sidebar.height() / 5) as i32
+            + PADDING as i32
+            + (index / 6) as i32 * size as i32,
+        size,
+        size,
+    )
+}
+
+// renders one line of text below the other, starting at the given point
+fn render_text_lines(
+    canvas: &mut WindowCanvas,
+    theme: &theme::Theme,
+    lines: &[String],
+    x: i32,
+    y: i32,
+    font: &sdl2::ttf::Font,
+    texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+) {
+    let mut y = y;
+    for line in lines.iter() {
+        let text = font.render(line).blended(theme.foreground).unwrap();
+        let texture =
+            texture_creator.create_texture_from_surface(text).unwrap();
+        let dimensions = texture.query();
+        let target = Rect::new(x, y, dimensions.width, dimensions.height);
+        canvas.copy(&texture, None, target).unwrap();
+        y += (dimensions.height + PADDING) as i32;
+    }
+}
+
 fn render_sidebar(
     canvas: &mut WindowCanvas,
+    theme: &theme::Theme,
     bounds: Rect,
     state: &GameState,
-    mediumfont: &sdl2::ttf::Font,
-    smallfont: &sdl2::ttf::Font,
+    fonts: &Fonts,
     sprites: &HashMap<field::Figure, sdl2::render::Texture>,
     texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
 ) {
     // draw sidebar border
-    canvas.set_draw_color(AUX_COLOR);
+    canvas.set_draw_color(theme.aux);
     canvas.fill_rect(bounds).unwrap();
-    canvas.set_draw_color(BG_COLOR);
+    canvas.set_draw_color(theme.background);
     canvas
         .fill_rect(Rect::new(
             bounds.x() + BORDER_WIDTH,
@@ -290,11 +875,15 @@ fn render_sidebar(
     // TOP PART
 
     // first line
-    let text_str = match state.whites_turn {
-        true => "White's",
-        false => "Black's",
+    let text_str = match state.game.to_move {
+        field::FigureColor::White => "White's",
+        field::FigureColor::Black => "Black's",
     };
-    let text = mediumfont.render(text_str).blended(FG_COLOR).unwrap();
+    let text = fonts
+        .medium
+        .render(text_str)
+        .blended(theme.foreground)
+        .unwrap();
     let texture = texture_creator.create_texture_from_surface(text).unwrap();
     let fl_dimensions = texture.query();
     let target = Rect::new(
@@ -306,7 +895,11 @@ fn render_sidebar(
     canvas.copy(&texture, None, target).unwrap();
 
     // second line
-    let text = mediumfont.render("turn").blended(FG_COLOR).unwrap();
+    let text = fonts
+        .medium
+        .render("turn")
+        .blended(theme.foreground)
+        .unwrap();
     let texture = texture_creator.create_texture_from_surface(text).unwrap();
     let sl_dimensions = texture.query();
     let target = Rect::new(
@@ -320,13 +913,110 @@ fn render_sidebar(
 
     // MIDDLE PART
     // TODO: notate move order
+    if let Some(session) = &state.puzzle {
+        let lines = [
+            format!("Puzzle {}", session.current + 1),
+            format!("Rating {}", session.profile.rating),
+            format!("Streak {}", session.profile.streak),
+            format!("Best {}", session.profile.best_streak),
+        ];
+
+        render_text_lines(
+            canvas,
+            theme,
+            &lines,
+            middle.x() + (PADDING * 2) as i32,
+            middle.y() + PADDING as i32,
+            &fonts.small,
+            texture_creator,
+        );
+    }
+
+    // the typed move and the legal moves it could be, shown in the highlight
+    // color when none matches
+    if let Some(text) = &state.input {
+        let suggestions = &state.suggestions;
+        let color = if !text.is_empty() && suggestions.is_empty() {
+            theme.highlight
+        } else {
+            theme.foreground
+        };
+
+        let x = middle.x() + (PADDING * 2) as i32;
+        let y = middle.y() + middle.height() as i32 / 2;
+        let surface = fonts
+            .small
+            .render(&format!("> {}_", text))
+            .blended(color)
+            .unwrap();
+        let texture = texture_creator
+            .create_texture_from_surface(&surface)
+            .unwrap();
+        let dimensions = texture.query();
+        let target = Rect::new(x, y, dimensions.width, dimensions.height);
+        canvas.copy(&texture, None, target).unwrap();
+
+        let lines: Vec<String> = suggestions
+            .iter()
+            .take(6)
+            .map(|(_, san)| format!("  {}", san))
+            .collect();
+        render_text_lines(
+            canvas,
+            theme,
+            &lines,
+            x,
+            y + (dimensions.height + PADDING) as i32,
+            &fonts.small,
+            texture_creator,
+        );
+    }
+
+    // figure palette and position settings of the editor
+    if let Some(editor) = &state.editor {
+        for (index, figure) in editor::PALETTE.iter().enumerate() {
+            let slot = palette_slot(bounds, index);
+            let sprite = sprites.get(figure).unwrap();
+            let target = Rect::new(
+                slot.x() + PADDING as i32,
+                slot.y() + PADDING as i32,
+                slot.width() - PADDING * 2,
+                slot.height() - PADDING * 2,
+            );
+            canvas.copy(sprite, None, target).unwrap();
+
+            if editor.selected == Some(*figure) {
+                canvas.set_draw_color(theme.highlight);
+                draw_frame(canvas, slot, BORDER_WIDTH as u32);
+            }
+        }
+
+        let en_passant = match editor.en_passant {
+            Some((x, y)) => field::square_name(x, y),
+            None => "-".to_string(),
+        };
+        let lines = [
+            format!("Castle {}", editor.castling_text()),
+            format!("E.p. {}", en_passant),
+        ];
+        let palette_end = palette_slot(bounds, editor::PALETTE.len() - 1);
+        render_text_lines(
+            canvas,
+            theme,
+            &lines,
+            middle.x() + (PADDING * 2) as i32,
+            palette_end.bottom() + PADDING as i32,
+            &fonts.small,
+            texture_creator,
+        );
+    }
 
     // BOTTOM PART
     // TODO: render pictograms of caputred pieces (WIP)
     let mut x = bottom.x() + PADDING as i32;
     let y = bottom.y() + PADDING as i32;
     let mut h = 0;
-    for piece in state.captured_white.iter() {
+    for piece in state.game.captured_white.iter() {
         let texture = sprites.get(piece).unwrap();
         let dimensions = texture.query();
 
@@ -338,7 +1028,7 @@ fn render_sidebar(
     }
 
     let y = bottom.y + bottom.height() as i32 - h as i32 - PADDING as i32;
-    for piece in state.captured_black.iter() {
+    for piece in state.game.captured_black.iter() {
         let texture = sprites.get(piece).unwrap();
         let dimensions = texture.query();
 
@@ -351,13 +1041,14 @@ fn render_sidebar(
 
 fn render_winning_screen(
     canvas: &mut WindowCanvas,
+    theme: &theme::Theme,
     state: &GameState,
     bounds: Rect,
     font: &sdl2::ttf::Font,
     texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
 ) {
     // fill background with border
-    canvas.set_draw_color(AUX_COLOR);
+    canvas.set_draw_color(theme.aux);
     canvas.fill_rect(bounds).unwrap();
 
     let internal_bounds = Rect::new(
@@ -366,18 +1057,21 @@ fn render_winning_screen(
         bounds.width() - (BORDER_WIDTH * 8) as u32,
         bounds.height() - (BORDER_WIDTH * 8) as u32,
     );
-    canvas.set_draw_color(BG_COLOR);
+    canvas.set_draw_color(theme.background);
     canvas.fill_rect(internal_bounds).unwrap();
 
-    let text = match state.checkmate {
-        true => match state.whites_turn {
-            false => "White won!",
-            true => "Black won!",
-        },
-        false => "Draw!",
+    let text = match state.game.outcome {
+        Some(game::Outcome::Checkmate(field::FigureColor::White)) => {
+            "White won!"
+        }
+        Some(game::Outcome::Checkmate(field::FigureColor::Black)) => {
+            "Black won!"
+        }
+        Some(game::Outcome::Stalemate) => "Stalemate!",
+        Some(game::Outcome::Draw) | None => "Draw!",
     };
 
-    let surface = font.render(text).blended(FG_COLOR).unwrap();
+    let surface = font.render(text).blended(theme.foreground).unwrap();
     let texture = texture_creator
         .create_texture_from_surface(&surface)
         .unwrap();
@@ -394,139 +1088,705 @@ fn render_winning_screen(
     canvas.copy(&texture, None, target).unwrap();
 }
 
+// draws a short message in a framed banner at the top of the given bounds
+fn render_banner<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    theme: &theme::Theme,
+    text: &str,
+    color: Color,
+    bounds: Rect,
+    font: &sdl2::ttf::Font,
+    texture_creator: &sdl2::render::TextureCreator<T::Context>,
+) {
+    let surface = font.render(text).blended(color).unwrap();
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .unwrap();
+    let dimensions = texture.query();
+
+    // banner at the top of the board, with border
+    let banner = Rect::from_center(
+        (bounds.center().x(), bounds.y() + bounds.height() as i32 / 8),
+        dimensions.width + PADDING * 8,
+        dimensions.height + PADDING * 4,
+    );
+    canvas.set_draw_color(theme.aux);
+    canvas.fill_rect(banner).unwrap();
+    canvas.set_draw_color(theme.background);
+    canvas
+        .fill_rect(Rect::new(
+            banner.x() + BORDER_WIDTH,
+            banner.y() + BORDER_WIDTH,
+            banner.width() - (BORDER_WIDTH * 2) as u32,
+            banner.height() - (BORDER_WIDTH * 2) as u32,
+        ))
+        .unwrap();
+
+    let target =
+        Rect::from_center(banner.center(), dimensions.width, dimensions.height);
+    canvas.copy(&texture, None, target).unwrap();
+}
+
+fn render_puzzle_feedback(
+    canvas: &mut WindowCanvas,
+    theme: &theme::Theme,
+    session: &puzzle::PuzzleSession,
+    bounds: Rect,
+    font: &sdl2::ttf::Font,
+    texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+) {
+    let (text, color) = match session.feedback {
+        Some(puzzle::Feedback::Correct) => ("Correct!", theme.foreground),
+        Some(puzzle::Feedback::Wrong) => ("Wrong!", theme.highlight),
+        Some(puzzle::Feedback::Solved) => ("Solved! Press N", theme.foreground),
+        None => return,
+    };
+
+    render_banner(canvas, theme, text, color, bounds, font, texture_creator);
+}
+
+// lists the themes, the one under the cursor highlighted and the active
+// one marked with a star, and the volume
+fn render_settings(
+    canvas: &mut WindowCanvas,
+    themes: &[theme::Theme],
+    theme_index: usize,
+    state: &GameState,
+    bounds: Rect,
+    fonts: &Fonts,
+    texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+) {
+    let theme = &themes[theme_index];
+
+    // fill background with border
+    canvas.set_draw_color(theme.aux);
+    canvas.fill_rect(bounds).unwrap();
+    let internal_bounds = Rect::new(
+        bounds.x() + BORDER_WIDTH * 4,
+        bounds.y() + BORDER_WIDTH * 4,
+        bounds.width() - (BORDER_WIDTH * 8) as u32,
+        bounds.height() - (BORDER_WIDTH * 8) as u32,
+    );
+    canvas.set_draw_color(theme.background);
+    canvas.fill_rect(internal_bounds).unwrap();
+
+    let mut lines =
+        vec![(&fonts.medium, "Themes".to_string(), theme.foreground)];
+    for (index, entry) in themes.iter().enumerate() {
+        let marker = if index == theme_index { "* " } else { "  " };
+        let color = if Some(index) == state.settings {
+            theme.highlight
+        } else {
+            theme.foreground
+        };
+        lines.push((&fonts.small, format!("{}{}", marker, entry.name), color));
+    }
+    lines.push((&fonts.small, "Enter: apply".to_string(), theme.aux));
+
+    let volume = if state.muted {
+        "Volume muted".to_string()
+    } else {
+        format!("Volume {}%", state.volume)
+    };
+    lines.push((&fonts.medium, volume, theme.foreground));
+    lines.push((&fonts.small, "Left/Right, M: mute".to_string(), theme.aux));
+
+    let x = internal_bounds.x() + (PADDING * 4) as i32;
+    let mut y = internal_bounds.y() + (PADDING * 4) as i32;
+    for (font, text, color) in lines {
+        let surface = font.render(&text).blended(color).unwrap();
+        let texture = texture_creator
+            .create_texture_from_surface(&surface)
+            .unwrap();
+        let dimensions = texture.query();
+        let target = Rect::new(x, y, dimensions.width, dimensions.height);
+        canvas.copy(&texture, None, target).unwrap();
+        y += (dimensions.height + PADDING * 2) as i32;
+    }
+}
+
 // the main render method
 fn render(
     canvas: &mut WindowCanvas,
+    themes: &[theme::Theme],
+    theme_index: usize,
     state: &GameState,
-    textures: &HashMap<field::Figure, sdl2::render::Texture>,
-    smallfont: &sdl2::ttf::Font,
-    mediumfont: &sdl2::ttf::Font,
-    bigfont: &sdl2::ttf::Font,
+    textures: &mut HashMap<field::Figure, sdl2::render::Texture>,
+    fonts: &Fonts,
     texture_creator: &sdl2::render::TextureCreator<sdl2::video::WindowContext>,
 ) {
+    let theme = &themes[theme_index];
     let screen_size = Rect::new(0, 0, WIDTH, HEIGHT);
     let field_bounds = Rect::new(0, 0, HEIGHT, HEIGHT);
     let sidebar_bounds = Rect::new(HEIGHT as i32, 0, WIDTH - HEIGHT, HEIGHT);
 
     render_field(
         canvas,
+        theme,
         field_bounds,
         state,
         textures,
-        smallfont,
+        &fonts.small,
         texture_creator,
     );
 
     render_sidebar(
         canvas,
+        theme,
         sidebar_bounds,
         state,
-        mediumfont,
-        smallfont,
+        fonts,
         textures,
         texture_creator,
     );
 
-    if state.checkmate || state.draw {
+    if let Some(editor) = &state.editor {
+        if let Some(error) = editor.error {
+            render_banner(
+                canvas,
+                theme,
+                &error.to_string(),
+                theme.highlight,
+                field_bounds,
+                &fonts.medium,
+                texture_creator,
+            );
+        }
+    } else if let Some(session) = &state.puzzle {
+        render_puzzle_feedback(
+            canvas,
+            theme,
+            session,
+            field_bounds,
+            &fonts.medium,
+            texture_creator,
+        );
+    } else if state.game.outcome.is_some() {
         let dialog_bounds =
             Rect::from_center(screen_size.center(), WIDTH / 2, HEIGHT / 3);
         render_winning_screen(
             canvas,
+            theme,
+            state,
+            dialog_bounds,
+            &fonts.big,
+            texture_creator,
+        );
+    }
+
+    if state.settings.is_some() {
+        let dialog_bounds =
+            Rect::from_center(screen_size.center(), WIDTH / 2, HEIGHT / 2);
+        render_settings(
+            canvas,
+            themes,
+            theme_index,
             state,
             dialog_bounds,
-            bigfont,
+            fonts,
             texture_creator,
         );
     }
     canvas.present();
 }
 
-fn load_sprites(
-    texture_creator: &'_ sdl2::render::TextureCreator<
-        sdl2::video::WindowContext,
-    >,
-) -> HashMap<field::Figure, sdl2::render::Texture> {
+// loads the sprites of a piece set, `dir` holding w_pawn.png, b_king.png, ...
+// or the embedded piece set without one
+fn load_sprites<'a, T>(
+    texture_creator: &'a sdl2::render::TextureCreator<T>,
+    dir: Option<&Path>,
+) -> Result<HashMap<field::Figure, sdl2::render::Texture<'a>>, String> {
     let mut sprites = HashMap::new();
+    // the editor palette holds every figure once
+    for figure in editor::PALETTE {
+        let name = assets::sprite_name(&figure);
+        let sprite = match dir {
+            Some(dir) => {
+                let path = dir.join(&name);
+                if !path.is_file() {
+                    return Err(format!("missing sprite {}", path.display()));
+                }
+                texture_creator.load_texture(&path).map_err(|e| {
+                    format!("could not load {}: {}", path.display(), e)
+                })?
+            }
+            None => {
+                let bytes = assets::sprite(&name)
+                    .ok_or_else(|| format!("no embedded sprite {}", name))?;
+                texture_creator.load_texture_bytes(bytes).map_err(|e| {
+                    format!("could not load embedded {}: {}", name, e)
+                })?
+            }
+        };
+        sprites.insert(figure, sprite);
+    }
+    Ok(sprites)
+}
+
+struct Fonts<'ttf> {
+    small: sdl2::ttf::Font<'ttf, 'static>,
+    medium: sdl2::ttf::Font<'ttf, 'static>,
+    big: sdl2::ttf::Font<'ttf, 'static>,
+}
+
+// loads a font, the embedded one without a path
+fn load_font<'ttf>(
+    ttf_context: &'ttf sdl2::ttf::Sdl2TtfContext,
+    path: Option<&Path>,
+    size: u16,
+) -> Result<sdl2::ttf::Font<'ttf, 'static>, String> {
+    match path {
+        Some(path) if !path.is_file() => {
+            Err(format!("missing font {}", path.display()))
+        }
+        Some(path) => ttf_context
+            .load_font(path, size)
+            .map_err(|e| format!("could not load {}: {}", path.display(), e)),
+        None => ttf_context
+            .load_font_from_rwops(RWops::from_bytes(assets::FONT)?, size)
+            .map_err(|e| {
+                format!("could not load embedded {}: {}", assets::FONT_NAME, e)
+            }),
+    }
+}
+
+// loads the small, medium and big variant of a font
+fn load_fonts<'ttf>(
+    ttf_context: &'ttf sdl2::ttf::Sdl2TtfContext,
+    path: Option<&Path>,
+) -> Result<Fonts<'ttf>, String> {
+    Ok(Fonts {
+        small: load_font(ttf_context, path, 24)?,
+        medium: load_font(ttf_context, path, 32)?,
+        big: load_font(ttf_context, path, 64)?,
+    })
+}
 
-    let mut load_sprite = |path: &str, figure_type: field::Figure| {
-        let sprite = texture_creator.load_texture(path).unwrap();
-        sprites.insert(figure_type, sprite);
+// sets up a position given as fen for the render and svg commands, with
+// the orientation, last move and arrows given as options
+fn position_from_options(
+    fen: &str,
+    options: &[String],
+) -> Result<GameState, String> {
+    let (field, to_move) =
+        field::Field::from_fen(fen).ok_or(format!("invalid fen: {}", fen))?;
+    let mut state = GameState::new(field, to_move);
+
+    state.flipped = match option_value(options, "orientation") {
+        None | Some("white") => false,
+        Some("black") => true,
+        Some(other) => return Err(format!("invalid --orientation: {}", other)),
     };
+    if let Some(text) = option_value(options, "last-move") {
+        let last_move = notation::parse_move(text)
+            .ok_or(format!("invalid --last-move: {}", text))?;
+        state.game.last_move = Some(last_move);
+    }
+    if let Some(text) = option_value(options, "arrows") {
+        let annotations = text
+            .split(',')
+            .map(|a| {
+                annotation::Annotation::parse(a)
+                    .ok_or(format!("invalid arrow: {}", a))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        state
+            .annotations
+            .insert(state.game.history.len(), annotations);
+    }
+    Ok(state)
+}
 
-    load_sprite(
-        "resources/sprites/w_pawn.png",
-        field::Figure::new(field::FigureColor::White, field::FigureType::Pawn),
-    );
-    load_sprite(
-        "resources/sprites/w_rook.png",
-        field::Figure::new(field::FigureColor::White, field::FigureType::Rook),
-    );
+// draws a position into a png image of `size` pixels through an offscreen
+// surface, neither a window nor a video driver is needed
+fn render_position(
+    theme: &theme::Theme,
+    state: &GameState,
+    path: &Path,
+    size: u32,
+) -> Result<(), String> {
+    let surface = Surface::new(size, size, PixelFormatEnum::ARGB8888)?;
+    let mut canvas = surface.into_canvas()?;
+    let texture_creator = canvas.texture_creator();
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let mut sprites = load_sprites(&texture_creator, theme.pieces.as_deref())?;
+    // the coordinates keep their size relative to the board
+    let font_size = (size * 24 / HEIGHT).max(8) as u16;
+    let font = load_font(&ttf_context, theme.font.as_deref(), font_size)?;
 
-    load_sprite(
-        "resources/sprites/b_pawn.png",
-        field::Figure::new(field::FigureColor::Black, field::FigureType::Pawn),
-    );
-    load_sprite(
-        "resources/sprites/b_rook.png",
-        field::Figure::new(field::FigureColor::Black, field::FigureType::Rook),
+    render_field(
+        &mut canvas,
+        theme,
+        Rect::new(0, 0, size, size),
+        state,
+        &mut sprites,
+        &font,
+        &texture_creator,
     );
+    canvas.present();
+    canvas.into_surface().save(path)
+}
 
-    load_sprite(
-        "resources/sprites/w_knight.png",
-        field::Figure::new(
-            field::FigureColor::White,
-            field::FigureType::Knight,
-        ),
-    );
+struct GifOptions {
+    size: u32,
+    // how long each position is shown
+    delay: Duration,
+    // the move leading to a position in a banner over the board
+    captions: bool,
+    flipped: bool,
+}
 
-    load_sprite(
-        "resources/sprites/b_knight.png",
-        field::Figure::new(
-            field::FigureColor::Black,
-            field::FigureType::Knight,
-        ),
-    );
+// replays the moves from a starting position into an animated gif, every
+// frame drawn like the `render` command draws a position
+fn export_gif(
+    theme: &theme::Theme,
+    start: &(field::Field, field::FigureColor),
+    moves: &[notation::Move],
+    path: &Path,
+    options: &GifOptions,
+) -> Result<(), String> {
+    let size = u16::try_from(options.size)
+        .map_err(|_| format!("a gif is at most {} pixels wide", u16::MAX))?;
+    let file = fs::File::create(path)
+        .map_err(|e| format!("could not create {}: {}", path.display(), e))?;
+    let mut encoder =
+        gif::Encoder::new(file, size, size, &[]).map_err(|e| e.to_string())?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|e| e.to_string())?;
+
+    let surface =
+        Surface::new(options.size, options.size, PixelFormatEnum::ARGB8888)?;
+    let mut canvas = surface.into_canvas()?;
+    let texture_creator = canvas.texture_creator();
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let mut sprites = load_sprites(&texture_creator, theme.pieces.as_deref())?;
+    let font_size = (options.size * 24 / HEIGHT).max(8) as u16;
+    let font = load_font(&ttf_context, theme.font.as_deref(), font_size)?;
+    let caption_size = (options.size * 32 / HEIGHT).max(8) as u16;
+    let caption_font =
+        load_font(&ttf_context, theme.font.as_deref(), caption_size)?;
+
+    let mut state = GameState::new(start.0.clone(), start.1);
+    state.flipped = options.flipped;
+    let black_started = start.1 == field::FigureColor::Black;
+    let bounds = Rect::new(0, 0, options.size, options.size);
+
+    // the starting position, then one frame per move
+    for index in 0..=moves.len() {
+        let mut caption = None;
+        if index > 0 {
+            let (from, to) = moves[index - 1];
+            // the move number counts from white's first move
+            let ply = index - 1 + black_started as usize;
+            let number = ply / 2 + 1;
+            let dots = if ply.is_multiple_of(2) { "." } else { "..." };
+            let san = notation::san(&state.game.field, (from, to));
+            caption = Some(format!("{}{} {}", number, dots, san));
+
+            make_move(&mut state, from, to);
+            state.animation = None;
+        }
 
-    load_sprite(
-        "resources/sprites/w_bishop.png",
-        field::Figure::new(
-            field::FigureColor::White,
-            field::FigureType::Bishop,
-        ),
-    );
+        render_field(
+            &mut canvas,
+            theme,
+            bounds,
+            &state,
+            &mut sprites,
+            &font,
+            &texture_creator,
+        );
+        if let (Some(caption), true) = (caption, options.captions) {
+            render_banner(
+                &mut canvas,
+                theme,
+                &caption,
+                theme.foreground,
+                bounds,
+                &caption_font,
+                &texture_creator,
+            );
+        }
 
-    load_sprite(
-        "resources/sprites/b_bishop.png",
-        field::Figure::new(
-            field::FigureColor::Black,
-            field::FigureType::Bishop,
+        let mut pixels = canvas.read_pixels(None, PixelFormatEnum::ABGR8888)?;
+        let mut frame =
+            gif::Frame::from_rgba_speed(size, size, &mut pixels, 10);
+        // gif delays are in hundredths of a second, the final position
+        // stays a while longer before the replay loops
+        let mut delay = options.delay.as_millis() / 10;
+        if index == moves.len() {
+            delay *= 3;
+        }
+        frame.delay = delay.min(u16::MAX as u128) as u16;
+        encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// the gif command, the moves are played from the start position or the one
+// given with --fen
+fn gif_command(
+    theme: &theme::Theme,
+    args: &[String],
+    options: &[String],
+) -> Result<(), String> {
+    let path = args
+        .first()
+        .ok_or("usage: rusty_chess gif <file.gif> <move>...")?;
+
+    let (field, to_move) = match option_value(options, "fen") {
+        Some(fen) => field::Field::from_fen(fen)
+            .ok_or(format!("invalid fen: {}", fen))?,
+        None => (
+            field::Field::get_start_position(),
+            field::FigureColor::White,
         ),
-    );
+    };
+    field.validate(to_move).map_err(|e| e.to_string())?;
+
+    // check every move in turn, as algebraic or coordinate notation
+    let mut board = field.clone();
+    let mut color = to_move;
+    let mut moves = Vec::new();
+    for text in &args[1..] {
+        let (from, to) = notation::parse(&board, color, text)
+            .ok_or(format!("illegal move: {}", text))?;
+        board
+            .move_figure(from.0, from.1, to.0, to.1)
+            .map_err(|e| e.to_string())?;
+        moves.push((from, to));
+        color = field::opposite(color);
+    }
 
-    load_sprite(
-        "resources/sprites/w_queen.png",
-        field::Figure::new(field::FigureColor::White, field::FigureType::Queen),
-    );
+    let size = size_option(options, GIF_SIZE)?;
+    let delay = match option_value(options, "delay-ms").map(str::parse) {
+        None => GIF_DELAY,
+        Some(Ok(ms)) => Duration::from_millis(ms),
+        Some(Err(..)) => return Err("invalid --delay-ms".to_string()),
+    };
+    let gif_options = GifOptions {
+        size,
+        delay,
+        captions: option_value(options, "captions") != Some("off"),
+        flipped: option_value(options, "orientation") == Some("black"),
+    };
 
-    load_sprite(
-        "resources/sprites/b_queen.png",
-        field::Figure::new(field::FigureColor::Black, field::FigureType::Queen),
-    );
+    let start = (field, to_move);
+    export_gif(theme, &start, &moves, Path::new(path), &gif_options)
+}
 
-    load_sprite(
-        "resources/sprites/w_king.png",
-        field::Figure::new(field::FigureColor::White, field::FigureType::King),
-    );
+// writes the board as svg diagram, highlighting the last move
+fn save_svg(
+    theme: &theme::Theme,
+    state: &GameState,
+    path: &Path,
+    size: u32,
+    coordinates: bool,
+    piece_url: Option<&str>,
+) -> Result<(), String> {
+    let options = svg::SvgOptions {
+        size,
+        flipped: state.flipped,
+        coordinates,
+        highlights: state
+            .game
+            .last_move
+            .map_or(Vec::new(), |(from, to)| vec![from, to]),
+        annotations: state
+            .annotations
+            .get(&state.game.history.len())
+            .cloned()
+            .unwrap_or_default(),
+        piece_url: piece_url.map(str::to_string),
+    };
+    let text = svg::to_svg(&state.game.field, theme, &options)?;
+    fs::write(path, text)
+        .map_err(|e| format!("could not write {}: {}", path.display(), e))
+}
 
-    load_sprite(
-        "resources/sprites/b_king.png",
-        field::Figure::new(field::FigureColor::Black, field::FigureType::King),
-    );
+// the image size in pixels of the render, svg and gif commands, every
+// size accepted here can be drawn by render_field
+fn size_option(options: &[String], default: u32) -> Result<u32, String> {
+    match option_value(options, "size").map(str::parse) {
+        None => Ok(default),
+        Some(Ok(size)) if size >= 64 => Ok(size),
+        Some(..) => Err("invalid --size, it takes at least 64 pixels".into()),
+    }
+}
 
-    sprites
+// value of a `--name=value` command line option
+fn option_value<'a>(options: &'a [String], name: &str) -> Option<&'a str> {
+    options.iter().find_map(|option| {
+        option
+            .strip_prefix("--")?
+            .strip_prefix(name)?
+            .strip_prefix('=')
+    })
 }
 
 pub fn main() {
+    // `rusty_chess puzzles <file.csv> [profile]` starts the puzzle trainer,
+    // `rusty_chess render ...` draws a position into an image, options are
+    // given anywhere as `--name=value`
+    let (options, args): (Vec<String>, Vec<String>) =
+        std::env::args().partition(|arg| arg.starts_with("--"));
+
+    let animation_duration = match option_value(&options, "animation-ms") {
+        Some(ms) => match ms.parse() {
+            Ok(ms) => Duration::from_millis(ms),
+            Err(..) => {
+                eprintln!("invalid --animation-ms: {}", ms);
+                process::exit(1);
+            }
+        },
+        None => ANIMATION_DURATION,
+    };
+
+    let volume = match option_value(&options, "volume") {
+        Some(volume) => match volume.parse() {
+            Ok(volume) if volume <= 100 => volume,
+            _ => {
+                eprintln!("invalid --volume: {}", volume);
+                process::exit(1);
+            }
+        },
+        None => VOLUME,
+    };
+
+    let puzzle_session = match args.get(1).map(String::as_str) {
+        Some("puzzles") => {
+            let path = match args.get(2) {
+                Some(path) => PathBuf::from(path),
+                None => {
+                    eprintln!(
+                        "usage: rusty_chess puzzles <file.csv|file.epd> [profile]"
+                    );
+                    process::exit(1);
+                }
+            };
+            let puzzles = match puzzle::load_puzzles(&path) {
+                Ok(puzzles) => puzzles,
+                Err(e) => {
+                    eprintln!("could not read {}: {}", path.display(), e);
+                    process::exit(1);
+                }
+            };
+            if puzzles.is_empty() {
+                eprintln!("no playable puzzles in {}", path.display());
+                process::exit(1);
+            }
+            let profile_path = args
+                .get(3)
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("puzzle_profile.txt"));
+            Some(puzzle::PuzzleSession::new(puzzles, profile_path))
+        }
+        _ => None,
+    };
+
+    // themes come from --themes=<file>, without one the embedded themes are
+    // used
+    let mut themes = match option_value(&options, "themes") {
+        Some(path) => match theme::load_themes(Path::new(path)) {
+            Ok(themes) => themes,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+        None => theme::parse_themes(assets::THEMES, Path::new(""))
+            .unwrap_or_else(|e| {
+                eprintln!("embedded themes: {}, using the classic theme", e);
+                vec![theme::Theme::default()]
+            }),
+    };
+
+    // --assets=<dir> replaces the embedded sprites and font of every theme
+    // not bringing its own with <dir>/sprites and <dir>/C64_Pro-STYLE.ttf,
+    // and the beeps with <dir>/sounds if there is one
+    if let Some(dir) = option_value(&options, "assets") {
+        let dir = Path::new(dir);
+        if !dir.is_dir() {
+            eprintln!("missing assets directory {}", dir.display());
+            process::exit(1);
+        }
+        for theme in themes.iter_mut() {
+            theme.pieces.get_or_insert_with(|| dir.join("sprites"));
+            theme
+                .font
+                .get_or_insert_with(|| dir.join(assets::FONT_NAME));
+            if dir.join("sounds").is_dir() {
+                theme.sounds.get_or_insert_with(|| dir.join("sounds"));
+            }
+        }
+    }
+    let mut theme_index = match option_value(&options, "theme") {
+        Some(name) => match themes.iter().position(|t| t.name == name) {
+            Some(index) => index,
+            None => {
+                eprintln!("unknown theme: {}", name);
+                process::exit(1);
+            }
+        },
+        None => 0,
+    };
+
+    // `rusty_chess render <fen> <file.png>` and `rusty_chess svg <fen>
+    // <file.svg>` draw a position without opening a window
+    let command = args.get(1).map(String::as_str);
+    if command == Some("render") || command == Some("svg") {
+        let (fen, path) = match (args.get(2), args.get(3)) {
+            (Some(fen), Some(path)) => (fen, Path::new(path)),
+            _ => {
+                eprintln!("usage: rusty_chess render|svg <fen> <file>");
+                process::exit(1);
+            }
+        };
+        let size = match size_option(&options, HEIGHT) {
+            Ok(size) => size,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        };
+        let coordinates = option_value(&options, "coordinates") != Some("off");
+        let piece_url = option_value(&options, "piece-url");
+
+        let theme = &themes[theme_index];
+        let result =
+            position_from_options(fen, &options).and_then(
+                |state| match command {
+                    Some("render") => {
+                        render_position(theme, &state, path, size)
+                    }
+                    _ => save_svg(
+                        theme,
+                        &state,
+                        path,
+                        size,
+                        coordinates,
+                        piece_url,
+                    ),
+                },
+            );
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    // `rusty_chess gif <file.gif> <move>...` replays a game into a gif
+    if command == Some("gif") {
+        let theme = &themes[theme_index];
+        if let Err(e) = gif_command(theme, &args[2..], &options) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
 
@@ -543,51 +1803,274 @@ pub fn main() {
     let mut event_pump = sdl_context.event_pump().unwrap();
     let texture_creator = canvas.texture_creator();
     let ttf_context = sdl2::ttf::init().unwrap();
-    let lspr = load_sprites(&texture_creator);
-    let lsmallfnt = ttf_context
-        .load_font("resources/C64_Pro-STYLE.ttf", 24)
-        .unwrap();
-    let lmediumfnt = ttf_context
-        .load_font("resources/C64_Pro-STYLE.ttf", 32)
-        .unwrap();
-    let lbigfnt = ttf_context
-        .load_font("resources/C64_Pro-STYLE.ttf", 64)
-        .unwrap();
+    let theme = &themes[theme_index];
+    let resources = load_sprites(&texture_creator, theme.pieces.as_deref())
+        .and_then(|sprites| {
+            Ok((sprites, load_fonts(&ttf_context, theme.font.as_deref())?))
+        });
+    let (mut lspr, mut fonts) = match resources {
+        Ok(resources) => resources,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    // the game stays silent without an audio device
+    let audio = match sound::open_audio() {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("could not open audio, sound is off: {}", e);
+            false
+        }
+    };
+    let sounds = audio
+        .then(|| sound::Sounds::load(theme.sounds.as_deref()))
+        .transpose();
+    let mut sounds = match sounds {
+        Ok(sounds) => sounds,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
 
     let mut state = GameState {
-        field: field::Field::get_start_position(),
-        whites_turn: true,
-        captured_white: Vec::new(),
-        captured_black: Vec::new(),
-        marked: None,
-        possible_moves: HashSet::new(),
-        checkmate: false,
-        draw: false,
+        animation_duration,
+        puzzle: puzzle_session,
+        volume,
+        ..GameState::new(
+            field::Field::get_start_position(),
+            field::FigureColor::White,
+        )
     };
+    start_puzzle(&mut state);
 
     //Main Loop
     let mut previous_buttons = HashSet::new();
+    let mut arrow_start = None;
     'running: loop {
+        let frame_start = Instant::now();
+
         canvas.set_draw_color(Color::RGB(0, 0, 0));
         canvas.clear();
+        update_suggestions(&mut state);
         render(
             &mut canvas,
+            &themes,
+            theme_index,
             &state,
-            &lspr,
-            &lsmallfnt,
-            &lmediumfnt,
-            &lbigfnt,
+            &mut lspr,
+            &fonts,
             &texture_creator,
         );
 
+        // the last frame of an animation has been drawn
+        let animation_done = state
+            .animation
+            .as_ref()
+            .is_some_and(|a| a.started.elapsed() >= state.animation_duration);
+        if animation_done {
+            state.animation = None;
+        }
+
+        if let (Some(sound), Some(sounds)) = (state.sound.take(), &sounds) {
+            if !state.muted {
+                sounds.play(sound, state.volume);
+            }
+        }
+
         // Main event handler
         for event in event_pump.poll_iter() {
             match event {
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } if state.settings.is_some() => {
+                    let selected =
+                        handle_settings_key(&mut state, keycode, themes.len());
+                    if let Some(index) = selected {
+                        // switch piece set and font along with the colors,
+                        // keeping the old theme if they can't be loaded
+                        let theme = &themes[index];
+                        let resources = load_sprites(
+                            &texture_creator,
+                            theme.pieces.as_deref(),
+                        )
+                        .and_then(|sprites| {
+                            let fonts = load_fonts(
+                                &ttf_context,
+                                theme.font.as_deref(),
+                            )?;
+                            let sounds = audio
+                                .then(|| {
+                                    sound::Sounds::load(theme.sounds.as_deref())
+                                })
+                                .transpose()?;
+                            Ok((sprites, fonts, sounds))
+                        });
+                        match resources {
+                            Ok((sprites, new_fonts, new_sounds)) => {
+                                lspr = sprites;
+                                fonts = new_fonts;
+                                sounds = new_sounds;
+                                theme_index = index;
+                            }
+                            Err(e) => eprintln!("{}", e),
+                        }
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } if state.input.is_some() => {
+                    handle_input_key(&mut state, keycode)
+                }
+                Event::TextInput { text, .. } => {
+                    if let Some(input) = state.input.as_mut() {
+                        input.push_str(text.trim());
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } if state.editor.is_some() => {
+                    handle_editor_key(&mut state, keycode)
+                }
                 Event::Quit { .. }
                 | Event::KeyDown {
                     keycode: Some(Keycode::Escape),
                     ..
                 } => break 'running,
+                Event::KeyDown {
+                    keycode: Some(Keycode::E),
+                    ..
+                } if state.puzzle.is_none() => open_editor(&mut state),
+                Event::KeyDown {
+                    keycode: Some(Keycode::O),
+                    ..
+                } => state.settings = Some(theme_index),
+                Event::KeyDown {
+                    keycode: Some(Keycode::A),
+                    ..
+                } => {
+                    // toggle animations
+                    state.animation = None;
+                    state.animation_duration =
+                        if !state.animation_duration.is_zero() {
+                            Duration::ZERO
+                        } else if !animation_duration.is_zero() {
+                            animation_duration
+                        } else {
+                            ANIMATION_DURATION
+                        };
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::M),
+                    ..
+                } => state.muted = !state.muted,
+                Event::KeyDown {
+                    keycode: Some(Keycode::G),
+                    ..
+                } => {
+                    // replay the game so far into a gif next to the game
+                    let seconds = SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .map_or(0, |d| d.as_secs());
+                    let path = PathBuf::from(format!("game-{}.gif", seconds));
+                    let options = GifOptions {
+                        size: GIF_SIZE,
+                        delay: GIF_DELAY,
+                        captions: true,
+                        flipped: state.flipped,
+                    };
+                    let theme = &themes[theme_index];
+                    match export_gif(
+                        theme,
+                        &state.game.start,
+                        &state.game.history,
+                        &path,
+                        &options,
+                    ) {
+                        Ok(()) => println!("saved {}", path.display()),
+                        Err(e) => eprintln!("{}", e),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::P),
+                    ..
+                } => {
+                    // save the game with its annotations next to the game
+                    let seconds = SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .map_or(0, |d| d.as_secs());
+                    let path = PathBuf::from(format!("game-{}.pgn", seconds));
+                    let text = pgn::to_pgn(&state.game, &state.annotations);
+                    match fs::write(&path, text) {
+                        Ok(()) => println!("saved {}", path.display()),
+                        Err(e) => eprintln!(
+                            "could not write {}: {}",
+                            path.display(),
+                            e
+                        ),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::D),
+                    ..
+                } => {
+                    // save a diagram of the board next to the game
+                    let seconds = SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .map_or(0, |d| d.as_secs());
+                    let path =
+                        PathBuf::from(format!("diagram-{}.svg", seconds));
+                    let theme = &themes[theme_index];
+                    match save_svg(theme, &state, &path, HEIGHT, true, None) {
+                        Ok(()) => println!("saved {}", path.display()),
+                        Err(e) => eprintln!("{}", e),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Return),
+                    ..
+                } => state.input = Some(String::new()),
+                Event::KeyDown {
+                    keycode:
+                        Some(
+                            keycode @ (Keycode::Left
+                            | Keycode::Right
+                            | Keycode::Up
+                            | Keycode::Down),
+                        ),
+                    ..
+                } => {
+                    let (dx, dy) = match keycode {
+                        Keycode::Left => (-1, 0),
+                        Keycode::Right => (1, 0),
+                        Keycode::Up => (0, -1),
+                        _ => (0, 1),
+                    };
+                    move_cursor(&mut state, dx, dy);
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Space),
+                    ..
+                } => {
+                    if let Some((x, y)) = state.cursor {
+                        select_square(&mut state, x, y);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::N),
+                    ..
+                } => {
+                    // skip to the next puzzle
+                    if let Some(session) = state.puzzle.as_mut() {
+                        session.advance();
+                        start_puzzle(&mut state);
+                    }
+                }
                 _ => {}
             }
         }
@@ -596,13 +2079,42 @@ pub fn main() {
             mouse_state.pressed_mouse_buttons().collect();
 
         let new_buttons = &buttons - &previous_buttons;
-        // This might be useful in the future for dragging pieces
-        //let released_buttons = &previous_buttons - &buttons;
+        let released_buttons = &previous_buttons - &buttons;
+
+        // right-click drags draw arrows, or circle the square they started
+        // on, the color is picked with shift and alt
+        if new_buttons.contains(&MouseButton::Right) {
+            arrow_start = board_square(mouse_state.x(), mouse_state.y());
+        }
+        if released_buttons.contains(&MouseButton::Right) {
+            let end = board_square(mouse_state.x(), mouse_state.y());
+            if let (Some(from), Some(to)) = (arrow_start.take(), end) {
+                let mods = sdl_context.keyboard().mod_state();
+                let shift = mods.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                let alt = mods.intersects(Mod::LALTMOD | Mod::RALTMOD);
+                let color = match (shift, alt) {
+                    (false, false) => annotation::AnnotationColor::Green,
+                    (true, false) => annotation::AnnotationColor::Red,
+                    (false, true) => annotation::AnnotationColor::Blue,
+                    (true, true) => annotation::AnnotationColor::Yellow,
+                };
+
+                let annotations = state
+                    .annotations
+                    .entry(state.game.history.len())
+                    .or_default();
+                annotation::toggle(
+                    annotations,
+                    annotation::Annotation::from_drag(from, to, color),
+                );
+            }
+        }
 
         if new_buttons.contains(&MouseButton::Left) {
             // Clicks
             let x = mouse_state.x();
             let y = mouse_state.y();
+            let square = board_square(x, y);
 
             if x < 0 || y < 0 {
                 // ignore clicks outside the window
@@ -612,77 +2124,53 @@ pub fn main() {
             let x = x as u32;
             let y = y as u32;
 
-            // check if click is in field
-            if x < HEIGHT && y < HEIGHT && !state.checkmate {
-                let field_x: u32 = x / (HEIGHT / 8);
-                let field_y: u32 = y / (HEIGHT / 8);
-
-                let mut marked = Some((field_x, field_y));
-                let players_color = if state.whites_turn {
-                    field::FigureColor::White
-                } else {
-                    field::FigureColor::Black
-                };
-
-                let opponent_color = if state.whites_turn {
-                    field::FigureColor::Black
-                } else {
-                    field::FigureColor::White
-                };
-
-                // if there is a figure on the clicked field, show possible moves
-                if let Some(figure) = state.field.get(field_x, field_y) {
-                    // if the clicked figure is not the current player's, ignore
-                    let is_players_figure = match state.whites_turn {
-                        true => figure.color == field::FigureColor::White,
-                        false => figure.color == field::FigureColor::Black,
-                    };
-                    if is_players_figure {
-                        state.possible_moves = state.field.get_possible_moves(
+            // the editor takes over all clicks, otherwise check if click is
+            // in field
+            if let Some(editor) = state.editor.as_mut() {
+                if let Some((field_x, field_y)) = square {
+                    // shift-click marks the en passant square
+                    let shift = sdl_context
+                        .keyboard()
+                        .mod_state()
+                        .intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                    if shift {
+                        editor.toggle_en_passant(field_x, field_y);
+                    } else {
+                        editor.click_square(
+                            &mut state.game.field,
                             field_x,
                             field_y,
-                            players_color,
                         );
-                    } else {
-                        if !state.possible_moves.contains(&(field_x, field_y)) {
-                            marked = None;
-                            state.possible_moves.clear();
-                        }
                     }
-                }
-                if let Some((old_x, old_y)) = state.marked {
-                    if state.possible_moves.contains(&(field_x, field_y)) {
-                        // see if we are about to capture a figure
-                        if let Some(figure) = state.field.get(field_x, field_y)
-                        {
-                            if figure.color == opponent_color {
-                                if figure.color == field::FigureColor::White {
-                                    state.captured_white.push(figure.clone());
-                                } else {
-                                    state.captured_black.push(figure.clone());
-                                }
+                } else {
+                    let sidebar =
+                        Rect::new(HEIGHT as i32, 0, WIDTH - HEIGHT, HEIGHT);
+                    for (index, figure) in editor::PALETTE.iter().enumerate() {
+                        let slot = palette_slot(sidebar, index);
+                        if slot.contains_point((x as i32, y as i32)) {
+                            // clicking the selected figure again deselects it
+                            if editor.selected == Some(*figure) {
+                                editor.selected = None;
+                            } else {
+                                editor.selected = Some(*figure);
                             }
                         }
-
-                        // move figure
-                        state.field.move_figure(old_x, old_y, field_x, field_y);
-
-                        marked = None;
-                        state.whites_turn = !state.whites_turn;
-                        state.possible_moves.clear();
                     }
                 }
-
-                // check for checkmate
-                state.checkmate = state.field.is_checkmate(opponent_color);
-                state.draw = state.field.is_draw();
-                state.marked = marked;
+            } else if let Some((x, y)) = square {
+                select_square(&mut state, x, y);
             }
         }
 
         previous_buttons = buttons;
 
         canvas.present();
-        std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 30));
+
+        // sleep for what is left of the frame, however long this one took
+        if let Some(remaining) =
+            FRAME_DURATION.checked_sub(frame_start.elapsed())
+        {
+            std::thread::sleep(remaining);
+        }
     }
 }
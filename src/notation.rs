@@ -0,0 +1,204 @@
+use crate::field::{
+    opposite, parse_square, square_name, Field, FigureColor, FigureType,
+};
+
+// the squares a figure moves from and to
+pub type Move = ((u32, u32), (u32, u32));
+
+// parses a move in coordinate notation, e.g. "e2e4"
+pub fn parse_move(text: &str) -> Option<Move> {
+    let (from, to) = (text.get(..2)?, text.get(2..)?);
+    Some((parse_square(from)?, parse_square(to)?))
+}
+
+// every legal move of the given color
+pub fn legal_moves(field: &Field, color: FigureColor) -> Vec<Move> {
+    let mut moves = Vec::new();
+    for y in 0..8 {
+        for x in 0..8 {
+            if field.get(x, y).is_some_and(|figure| figure.color == color) {
+                let mut targets: Vec<(u32, u32)> =
+                    field.get_possible_moves(x, y, color).into_iter().collect();
+                targets.sort();
+                moves.extend(targets.into_iter().map(|to| ((x, y), to)));
+            }
+        }
+    }
+    moves
+}
+
+fn piece_letter(figure_type: FigureType) -> &'static str {
+    match figure_type {
+        FigureType::Pawn => "",
+        FigureType::Rook => "R",
+        FigureType::Knight => "N",
+        FigureType::Bishop => "B",
+        FigureType::Queen => "Q",
+        FigureType::King => "K",
+    }
+}
+
+// the move in standard algebraic notation, e.g. "Nxf3+", the move has to be
+// legal in the given position
+pub fn san(field: &Field, mv: Move) -> String {
+    let ((from_x, from_y), (to_x, to_y)) = mv;
+    let figure = match field.get(from_x, from_y) {
+        Some(figure) => *figure,
+        None => return String::new(),
+    };
+    let capture = field.get(to_x, to_y).is_some();
+    let from_name = square_name(from_x, from_y);
+
+    let mut text = piece_letter(figure.figure_type).to_string();
+    if figure.figure_type == FigureType::Pawn {
+        if capture {
+            text.push_str(&from_name[..1]);
+        }
+    } else {
+        // name the file, rank or both of the moving figure if another one
+        // of the same kind could move to the same square
+        let others: Vec<(u32, u32)> = legal_moves(field, figure.color)
+            .into_iter()
+            .filter(|(from, to)| {
+                *to == (to_x, to_y)
+                    && *from != (from_x, from_y)
+                    && field.get(from.0, from.1) == Some(&figure)
+            })
+            .map(|(from, _)| from)
+            .collect();
+        if !others.is_empty() {
+            if others.iter().all(|(x, _)| *x != from_x) {
+                text.push_str(&from_name[..1]);
+            } else if others.iter().all(|(_, y)| *y != from_y) {
+                text.push_str(&from_name[1..]);
+            } else {
+                text.push_str(&from_name);
+            }
+        }
+    }
+    if capture {
+        text.push('x');
+    }
+    text.push_str(&square_name(to_x, to_y));
+
+    let mut board = field.clone();
+    let opponent = opposite(figure.color);
+    if board.move_figure(from_x, from_y, to_x, to_y).is_ok()
+        && board.is_check(opponent) == Ok(true)
+    {
+        text.push(if board.is_checkmate(opponent) {
+            '#'
+        } else {
+            '+'
+        });
+    }
+    text
+}
+
+// drops what is optional when typing a move: captures, check marks,
+// annotations and the dash of "e2-e4"
+fn normalize(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter(|c| !matches!(c, 'x' | '+' | '#' | '!' | '?' | '-'))
+        .collect()
+}
+
+// the legal moves whose san or coordinate notation starts with the typed
+// text, as (move, san) pairs, "Nf" matches "Nxf3" as well
+pub fn suggestions(
+    field: &Field,
+    color: FigureColor,
+    text: &str,
+) -> Vec<(Move, String)> {
+    let typed = text.trim().replace('-', "");
+    legal_moves(field, color)
+        .into_iter()
+        .map(|mv| (mv, san(field, mv)))
+        .filter(|((from, to), san)| {
+            let coordinates = format!(
+                "{}{}",
+                square_name(from.0, from.1),
+                square_name(to.0, to.1)
+            );
+            san.starts_with(&typed)
+                || normalize(san).starts_with(&typed)
+                || coordinates.starts_with(&typed)
+        })
+        .collect()
+}
+
+// parses a typed move in san ("Nf3") or coordinate notation ("g1f3"),
+// None unless it names exactly one legal move
+pub fn parse(field: &Field, color: FigureColor, text: &str) -> Option<Move> {
+    let typed = normalize(text);
+    if let Some(mv) = parse_move(&typed) {
+        return legal_moves(field, color).contains(&mv).then_some(mv);
+    }
+
+    let mut matches = legal_moves(field, color)
+        .into_iter()
+        .filter(|mv| normalize(&san(field, *mv)) == typed);
+    match (matches.next(), matches.next()) {
+        (Some(mv), None) => Some(mv),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(fen: &str) -> Field {
+        Field::from_fen(fen).unwrap().0
+    }
+
+    fn san_of(fen: &str, mv: &str) -> String {
+        san(&position(fen), parse_move(mv).unwrap())
+    }
+
+    #[test]
+    fn pawn_captures_name_the_file() {
+        let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w";
+        assert_eq!(san_of(fen, "e4d5"), "exd5");
+        assert_eq!(san_of(fen, "e4e5"), "e5");
+    }
+
+    #[test]
+    fn disambiguation() {
+        // knights on b1 and f1
+        assert_eq!(san_of("4k3/8/8/8/8/8/8/1N2KN2 w", "b1d2"), "Nbd2");
+        // rooks on a1 and a5
+        assert_eq!(san_of("4k3/8/8/R7/8/8/8/R3K3 w", "a1a3"), "R1a3");
+        // queens on a1, c1 and a3
+        assert_eq!(san_of("4k3/8/8/8/8/Q7/8/Q1Q1K3 w", "a1b2"), "Qa1b2");
+        // no other rook reaches a3
+        assert_eq!(san_of("4k3/8/8/8/8/8/8/R3K3 w", "a1a3"), "Ra3");
+    }
+
+    #[test]
+    fn check_and_mate_suffixes() {
+        assert_eq!(san_of("4k3/8/8/8/8/8/8/R3K3 w", "a1a8"), "Ra8+");
+        assert_eq!(san_of("4k3/8/4K3/8/8/8/8/R7 w", "a1a8"), "Ra8#");
+    }
+
+    #[test]
+    fn parses_coordinates() {
+        let field = Field::get_start_position();
+        let white = FigureColor::White;
+        assert_eq!(parse(&field, white, "e2-e4"), parse_move("e2e4"));
+        assert_eq!(parse(&field, white, "g1f3"), parse_move("g1f3"));
+        assert_eq!(parse(&field, white, "Nf3"), parse_move("g1f3"));
+        // not legal
+        assert_eq!(parse(&field, white, "e2e5"), None);
+    }
+
+    #[test]
+    fn ambiguous_san_is_rejected() {
+        let field = position("4k3/8/8/8/8/8/8/1N2KN2 w");
+        let white = FigureColor::White;
+        assert_eq!(parse(&field, white, "Nd2"), None);
+        assert_eq!(parse(&field, white, "Nbd2"), parse_move("b1d2"));
+        assert_eq!(parse(&field, white, "Nfd2"), parse_move("f1d2"));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::annotation::Annotation;
+use crate::field::{opposite, Field, FigureColor};
+use crate::game::{Game, Outcome};
+
+use std::collections::HashMap;
+
+// movetext lines are wrapped at this width
+static LINE_WIDTH: usize = 80;
+
+// the arrows and circles of a position as lichess style comment, e.g.
+// "{ [%csl Rd5][%cal Ge2e4] }"
+fn comment(annotations: &[Annotation]) -> Option<String> {
+    let (circles, arrows): (Vec<&Annotation>, Vec<&Annotation>) = annotations
+        .iter()
+        .partition(|a| matches!(a, Annotation::Circle { .. }));
+    let join = |annotations: Vec<&Annotation>| {
+        annotations
+            .iter()
+            .map(|a| a.text())
+            .collect::<Vec<String>>()
+            .join(",")
+    };
+
+    let mut text = String::new();
+    if !circles.is_empty() {
+        text.push_str(&format!("[%csl {}]", join(circles)));
+    }
+    if !arrows.is_empty() {
+        text.push_str(&format!("[%cal {}]", join(arrows)));
+    }
+    (!text.is_empty()).then(|| format!("{{ {} }}", text))
+}
+
+// the game as pgn, `annotations` are keyed by the number of moves played
+// before the position they belong to
+pub fn to_pgn(
+    game: &Game,
+    annotations: &HashMap<usize, Vec<Annotation>>,
+) -> String {
+    let mut color = game.start.1;
+
+    let mut tokens = Vec::new();
+    let position_comment =
+        |ply: usize| annotations.get(&ply).and_then(|a| comment(a));
+    // annotations of the start position come before the first move
+    tokens.extend(position_comment(0));
+
+    let mut number = 1;
+    let mut needs_number = true;
+    for (ply, san) in game.san_history().into_iter().enumerate() {
+        if color == FigureColor::White {
+            tokens.push(format!("{}.", number));
+        } else if needs_number {
+            tokens.push(format!("{}...", number));
+        }
+        tokens.push(san);
+
+        if color == FigureColor::Black {
+            number += 1;
+        }
+        color = opposite(color);
+
+        // black's move needs its number again after a comment
+        let comment = position_comment(ply + 1);
+        needs_number = comment.is_some();
+        tokens.extend(comment);
+    }
+
+    let result = game.outcome.map_or("*", Outcome::result);
+    tokens.push(result.to_string());
+
+    let mut pgn = String::new();
+    for (tag, value) in [
+        ("Event", "?"),
+        ("Site", "?"),
+        ("Date", "????.??.??"),
+        ("Round", "?"),
+        ("White", "?"),
+        ("Black", "?"),
+        ("Result", result),
+    ] {
+        pgn.push_str(&format!("[{} \"{}\"]\n", tag, value));
+    }
+    if game.start != (Field::get_start_position(), FigureColor::White) {
+        pgn.push_str("[SetUp \"1\"]\n");
+        pgn.push_str(&format!("[FEN \"{}\"]\n", game.start_fen()));
+    }
+    pgn.push('\n');
+
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+            pgn.push_str(&line);
+            pgn.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+    pgn.push_str(&line);
+    pgn.push('\n');
+    pgn
+}
@@ -0,0 +1,397 @@
+use crate::field::{opposite, Field, FigureColor};
+use crate::notation::{self, parse_move, Move};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+static DEFAULT_RATING: i32 = 1500;
+static RATING_K_FACTOR: f32 = 32.0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    pub id: String,
+    pub field: Field,
+    pub to_move: FigureColor,
+    // the first move is the opponent's, the player answers from the second
+    // on, unless `player_starts`
+    pub moves: Vec<Move>,
+    pub rating: i32,
+    // the side to move is the player's, as in epd files
+    pub player_starts: bool,
+}
+
+impl Puzzle {
+    // parses a line in the lichess puzzle csv format:
+    // PuzzleId,FEN,Moves,Rating,...
+    pub fn from_csv_line(line: &str) -> Option<Self> {
+        let columns: Vec<&str> = line.split(',').collect();
+        if columns.len() < 3 {
+            return None;
+        }
+
+        let (field, to_move) = Field::from_fen(columns[1])?;
+        let moves = columns[2]
+            .split_whitespace()
+            .map(parse_move)
+            .collect::<Option<Vec<Move>>>()?;
+        let rating = columns
+            .get(3)
+            .and_then(|r| r.trim().parse().ok())
+            .unwrap_or(DEFAULT_RATING);
+
+        let puzzle = Self {
+            id: columns[0].to_string(),
+            field,
+            to_move,
+            moves,
+            rating,
+            player_starts: false,
+        };
+
+        if puzzle.is_playable() {
+            Some(puzzle)
+        } else {
+            None
+        }
+    }
+
+    // parses an epd line, the position followed by operations, e.g.
+    // `6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id "back rank";`, the best
+    // move (the first one if several are given) is the solution
+    pub fn from_epd_line(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let position: Vec<&str> = words.by_ref().take(4).collect();
+        if position.len() < 4 {
+            return None;
+        }
+        let (field, to_move) = Field::from_fen(&position.join(" "))?;
+
+        let mut id = position[0].to_string();
+        let mut best_move = None;
+        let operations = words.collect::<Vec<&str>>().join(" ");
+        for operation in operations.split(';') {
+            let mut operands = operation.split_whitespace();
+            match operands.next() {
+                Some("bm") => {
+                    best_move = operands
+                        .next()
+                        .and_then(|san| notation::parse(&field, to_move, san))
+                }
+                Some("id") => {
+                    id = operands
+                        .collect::<Vec<&str>>()
+                        .join(" ")
+                        .trim_matches('"')
+                        .to_string()
+                }
+                _ => {}
+            }
+        }
+
+        let puzzle = Self {
+            id,
+            field,
+            to_move,
+            moves: vec![best_move?],
+            rating: DEFAULT_RATING,
+            player_starts: true,
+        };
+
+        if puzzle.is_playable() {
+            Some(puzzle)
+        } else {
+            None
+        }
+    }
+
+    pub fn players_color(&self) -> FigureColor {
+        if self.player_starts {
+            self.to_move
+        } else {
+            opposite(self.to_move)
+        }
+    }
+
+    // whether the move at `index` of the solution is played for the opponent
+    fn is_opponents_move(&self, index: usize) -> bool {
+        index.is_multiple_of(2) != self.player_starts
+    }
+
+    // checks that every move of the solution is legal with the rules we
+    // support, puzzles relying on castling, en passant or promotion are not
+    fn is_playable(&self) -> bool {
+        let players_moves = (0..self.moves.len())
+            .filter(|&index| !self.is_opponents_move(index))
+            .count();
+        if players_moves == 0 || self.field.validate(self.to_move).is_err() {
+            return false;
+        }
+
+        let mut field = self.field.clone();
+        let mut color = self.to_move;
+        for ((from_x, from_y), (to_x, to_y)) in self.moves.iter().copied() {
+            let owned = field
+                .get(from_x, from_y)
+                .is_some_and(|figure| figure.color == color);
+            if !owned
+                || !field
+                    .get_possible_moves(from_x, from_y, color)
+                    .contains(&(to_x, to_y))
+            {
+                return false;
+            }
+
+            if field.move_figure(from_x, from_y, to_x, to_y).is_err() {
+                return false;
+            }
+            color = opposite(color);
+        }
+        true
+    }
+}
+
+// reads puzzles from an .epd file, or from a csv file in the lichess format
+pub fn load_puzzles(path: &Path) -> io::Result<Vec<Puzzle>> {
+    let content = fs::read_to_string(path)?;
+    let parse: fn(&str) -> Option<Puzzle> =
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("epd") => Puzzle::from_epd_line,
+            _ => Puzzle::from_csv_line,
+        };
+
+    // malformed or unplayable lines (and the csv header) are skipped
+    Ok(content.lines().filter_map(parse).collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub rating: i32,
+    pub streak: u32,
+    pub best_streak: u32,
+    pub solved: u32,
+    pub failed: u32,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self {
+            rating: DEFAULT_RATING,
+            streak: 0,
+            best_streak: 0,
+            solved: 0,
+            failed: 0,
+        }
+    }
+
+    // reads a profile stored as `key=value` lines, missing files or keys
+    // fall back to the defaults
+    pub fn load(path: &Path) -> Self {
+        let mut profile = Self::new();
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(..) => return profile,
+        };
+
+        for line in content.lines() {
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            match key {
+                "rating" => {
+                    profile.rating = value.parse().unwrap_or(DEFAULT_RATING)
+                }
+                "streak" => profile.streak = value.parse().unwrap_or(0),
+                "best_streak" => {
+                    profile.best_streak = value.parse().unwrap_or(0)
+                }
+                "solved" => profile.solved = value.parse().unwrap_or(0),
+                "failed" => profile.failed = value.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+        profile
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(
+            path,
+            format!(
+                "rating={}\nstreak={}\nbest_streak={}\nsolved={}\nfailed={}\n",
+                self.rating,
+                self.streak,
+                self.best_streak,
+                self.solved,
+                self.failed
+            ),
+        )
+    }
+
+    // elo style update, treating the puzzle as the opponent
+    pub fn record(&mut self, puzzle_rating: i32, solved: bool) {
+        let expected = 1.0
+            / (1.0 + 10f32.powf((puzzle_rating - self.rating) as f32 / 400.0));
+        let score = if solved { 1.0 } else { 0.0 };
+        self.rating += (RATING_K_FACTOR * (score - expected)).round() as i32;
+
+        if solved {
+            self.solved += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.failed += 1;
+            self.streak = 0;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feedback {
+    Correct,
+    Wrong,
+    Solved,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PuzzleSession {
+    pub puzzles: Vec<Puzzle>,
+    pub current: usize,
+    // index of the next expected move in the current puzzle
+    pub progress: usize,
+    pub failed: bool,
+    pub feedback: Option<Feedback>,
+    pub profile: Profile,
+    pub profile_path: PathBuf,
+}
+
+impl PuzzleSession {
+    pub fn new(puzzles: Vec<Puzzle>, profile_path: PathBuf) -> Self {
+        Self {
+            puzzles,
+            current: 0,
+            progress: 0,
+            failed: false,
+            feedback: None,
+            profile: Profile::load(&profile_path),
+            profile_path,
+        }
+    }
+
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzles[self.current]
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.progress >= self.puzzle().moves.len()
+    }
+
+    // switches to the next puzzle, wrapping around at the end
+    pub fn advance(&mut self) {
+        self.current = (self.current + 1) % self.puzzles.len();
+        self.progress = 0;
+        self.failed = false;
+        self.feedback = None;
+    }
+
+    // returns the next move if it is the opponent's turn to play it
+    pub fn opponent_reply(&mut self) -> Option<Move> {
+        if self.is_solved() || !self.puzzle().is_opponents_move(self.progress) {
+            return None;
+        }
+        let reply = self.puzzle().moves[self.progress];
+        self.progress += 1;
+        Some(reply)
+    }
+
+    // checks a move of the player against the solution, a different move
+    // giving checkmate is accepted as well
+    pub fn check_move(&mut self, field: &Field, mv: Move) -> bool {
+        let ((from_x, from_y), (to_x, to_y)) = mv;
+        let players_color = self.puzzle().players_color();
+
+        let mut board = field.clone();
+        let opponent_color = opposite(players_color);
+        let is_mate = board.move_figure(from_x, from_y, to_x, to_y).is_ok()
+            && board.is_check(opponent_color) == Ok(true)
+            && board.is_checkmate(opponent_color);
+
+        if self.puzzle().moves.get(self.progress) != Some(&mv) && !is_mate {
+            if !self.failed {
+                self.failed = true;
+                self.finish(false);
+            }
+            self.feedback = Some(Feedback::Wrong);
+            return false;
+        }
+
+        self.progress += 1;
+        if is_mate {
+            self.progress = self.puzzle().moves.len();
+        }
+
+        if self.is_solved() {
+            if !self.failed {
+                self.finish(true);
+            }
+            self.feedback = Some(Feedback::Solved);
+        } else {
+            self.feedback = Some(Feedback::Correct);
+        }
+        true
+    }
+
+    fn finish(&mut self, solved: bool) {
+        let rating = self.puzzle().rating;
+        self.profile.record(rating, solved);
+        if let Err(e) = self.profile.save(&self.profile_path) {
+            eprintln!("could not save puzzle profile: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_puzzles_start_with_the_opponents_move() {
+        let line = "p1,6k1/5ppp/8/8/8/8/r4PP1/6K1 b - - 0 1,a2a1 g1h2,1200";
+        let puzzle = Puzzle::from_csv_line(line).unwrap();
+        assert_eq!(puzzle.players_color(), FigureColor::White);
+        assert_eq!(puzzle.rating, 1200);
+        assert!(puzzle.is_opponents_move(0));
+        assert!(!puzzle.is_opponents_move(1));
+        // the player needs a move to answer with
+        assert_eq!(
+            Puzzle::from_csv_line("p2,6k1/8/8/8/8/8/r7/6K1 b,a2a1"),
+            None
+        );
+    }
+
+    #[test]
+    fn epd_puzzles_start_with_the_players_move() {
+        let line = "6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id \"back rank\";";
+        let puzzle = Puzzle::from_epd_line(line).unwrap();
+        assert_eq!(puzzle.id, "back rank");
+        assert_eq!(puzzle.players_color(), FigureColor::White);
+        assert_eq!(puzzle.moves, vec![parse_move("a1a8").unwrap()]);
+        assert!(!puzzle.is_opponents_move(0));
+
+        // an illegal best move or none at all
+        assert_eq!(
+            Puzzle::from_epd_line("6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Rb8;"),
+            None
+        );
+        assert_eq!(
+            Puzzle::from_epd_line("6k1/5ppp/8/8/8/8/8/R5K1 w - -"),
+            None
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use sdl2::mixer::{self, AudioFormat, Channel, Chunk};
+
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::path::Path;
+
+static CHANNELS: i32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sound {
+    Move,
+    Capture,
+    Check,
+    GameOver,
+}
+
+static SOUNDS: [Sound; 4] =
+    [Sound::Move, Sound::Capture, Sound::Check, Sound::GameOver];
+
+impl Sound {
+    fn file_name(self) -> &'static str {
+        match self {
+            Sound::Move => "move.wav",
+            Sound::Capture => "capture.wav",
+            Sound::Check => "check.wav",
+            Sound::GameOver => "game_over.wav",
+        }
+    }
+
+    // pitch (hz) and length (ms) of the built in beep
+    fn beep(self) -> (f32, u32) {
+        match self {
+            Sound::Move => (440.0, 60),
+            Sound::Capture => (330.0, 120),
+            Sound::Check => (660.0, 150),
+            Sound::GameOver => (523.0, 400),
+        }
+    }
+}
+
+// opens the audio device, with SDL_AUDIODRIVER=dummy this works without
+// any sound hardware
+pub fn open_audio() -> Result<(), String> {
+    mixer::open_audio(
+        mixer::DEFAULT_FREQUENCY,
+        mixer::DEFAULT_FORMAT,
+        mixer::DEFAULT_CHANNELS,
+        1024,
+    )?;
+    mixer::allocate_channels(CHANNELS);
+    Ok(())
+}
+
+// appends a sample between -1 and 1 in the given audio format
+fn push_sample(
+    bytes: &mut Vec<u8>,
+    value: f32,
+    format: AudioFormat,
+) -> Result<(), String> {
+    match format {
+        mixer::AUDIO_U8 => bytes.push((value * 127.0 + 128.0) as u8),
+        mixer::AUDIO_S8 => bytes.push((value * 127.0) as i8 as u8),
+        mixer::AUDIO_U16LSB => bytes.extend_from_slice(
+            &((value * 32767.0 + 32768.0) as u16).to_le_bytes(),
+        ),
+        mixer::AUDIO_U16MSB => bytes.extend_from_slice(
+            &((value * 32767.0 + 32768.0) as u16).to_be_bytes(),
+        ),
+        mixer::AUDIO_S16LSB => {
+            bytes.extend_from_slice(&((value * 32767.0) as i16).to_le_bytes())
+        }
+        mixer::AUDIO_S16MSB => {
+            bytes.extend_from_slice(&((value * 32767.0) as i16).to_be_bytes())
+        }
+        mixer::AUDIO_S32LSB => bytes.extend_from_slice(
+            &((value as f64 * i32::MAX as f64) as i32).to_le_bytes(),
+        ),
+        mixer::AUDIO_S32MSB => bytes.extend_from_slice(
+            &((value as f64 * i32::MAX as f64) as i32).to_be_bytes(),
+        ),
+        mixer::AUDIO_F32LSB => bytes.extend_from_slice(&value.to_le_bytes()),
+        mixer::AUDIO_F32MSB => bytes.extend_from_slice(&value.to_be_bytes()),
+        _ => return Err(format!("unsupported audio format {:#x}", format)),
+    }
+    Ok(())
+}
+
+// a sine tone fading out, in the format the audio device was opened with,
+// which may differ from the one asked for in open_audio
+fn beep(pitch: f32, ms: u32) -> Result<Chunk, String> {
+    let (frequency, format, channels) = mixer::query_spec()?;
+    let frames = (frequency as u32 * ms / 1000) as usize;
+    let mut bytes = Vec::new();
+    for i in 0..frames {
+        let t = i as f32 / frequency as f32;
+        let fade = 1.0 - i as f32 / frames as f32;
+        let value = (TAU * pitch * t).sin() * fade * 0.25;
+        for _ in 0..channels {
+            push_sample(&mut bytes, value, format)?;
+        }
+    }
+    Chunk::from_raw_buffer(bytes.into_boxed_slice())
+}
+
+pub struct Sounds {
+    chunks: HashMap<Sound, Chunk>,
+}
+
+impl Sounds {
+    // loads move.wav, capture.wav, check.wav and game_over.wav from `dir`,
+    // or uses simple beeps without one
+    pub fn load(dir: Option<&Path>) -> Result<Self, String> {
+        let mut chunks = HashMap::new();
+        for sound in SOUNDS {
+            let chunk = match dir {
+                Some(dir) => {
+                    let path = dir.join(sound.file_name());
+                    if !path.is_file() {
+                        return Err(format!(
+                            "missing sound {}",
+                            path.display()
+                        ));
+                    }
+                    Chunk::from_file(&path).map_err(|e| {
+                        format!("could not load {}: {}", path.display(), e)
+                    })?
+                }
+                None => {
+                    let (pitch, ms) = sound.beep();
+                    beep(pitch, ms)?
+                }
+            };
+            chunks.insert(sound, chunk);
+        }
+        Ok(Self { chunks })
+    }
+
+    // plays a sound at the given volume in percent
+    pub fn play(&self, sound: Sound, volume: u8) {
+        if volume == 0 {
+            return;
+        }
+        Channel::all().set_volume(volume as i32 * mixer::MAX_VOLUME / 100);
+        // all channels busy is not worth a message
+        let _ = Channel::all().play(&self.chunks[&sound], 0);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::annotation::Annotation;
+use crate::assets;
+use crate::field::{square_name, Field};
+use crate::theme::Theme;
+
+use sdl2::pixels::Color;
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+
+pub struct SvgOptions {
+    pub size: u32,
+    // black at the bottom
+    pub flipped: bool,
+    pub coordinates: bool,
+    pub highlights: Vec<(u32, u32)>,
+    pub annotations: Vec<Annotation>,
+    // pieces are linked as `<piece_url>w_pawn.png`, ... instead of being
+    // embedded
+    pub piece_url: Option<String>,
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(n >> (18 - i * 6) & 63) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+// text for use inside a quoted attribute
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;")
+}
+
+// svg color and opacity attributes
+fn paint(attribute: &str, color: Color) -> String {
+    let mut text = format!(
+        "{}=\"#{:02x}{:02x}{:02x}\"",
+        attribute, color.r, color.g, color.b
+    );
+    if color.a != 255 {
+        write!(
+            text,
+            " {}-opacity=\"{:.3}\"",
+            attribute,
+            color.a as f32 / 255.0
+        )
+        .unwrap();
+    }
+    text
+}
+
+// an image of the position as svg, the piece set and colors are taken from
+// the theme
+pub fn to_svg(
+    field: &Field,
+    theme: &Theme,
+    options: &SvgOptions,
+) -> Result<String, String> {
+    let size = options.size as f32;
+    let square = size / 8.0;
+    let position = |(x, y): (u32, u32)| {
+        let (x, y) = if options.flipped {
+            (7 - x, 7 - y)
+        } else {
+            (x, y)
+        };
+        (x as f32 * square, y as f32 * square)
+    };
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+         xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+         width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">",
+        options.size
+    )
+    .unwrap();
+
+    for x in 0..8 {
+        for y in 0..8 {
+            let color = if (x + y) % 2 == 0 {
+                theme.light_square
+            } else {
+                theme.dark_square
+            };
+            let (left, top) = position((x, y));
+            let rect = format!(
+                "x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"",
+                left, top, square, square
+            );
+            writeln!(svg, "<rect {} {}/>", rect, paint("fill", color)).unwrap();
+            if options.highlights.contains(&(x, y)) {
+                writeln!(
+                    svg,
+                    "<rect {} {}/>",
+                    rect,
+                    paint("fill", theme.last_move)
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    if options.coordinates {
+        // numbers in the top left of the left column, letters in the bottom
+        // right of the bottom row, like on screen
+        let font_size = square * 0.2;
+        let label_color = |(x, y): (u32, u32)| {
+            if (x + y) % 2 == 0 {
+                theme.dark_square
+            } else {
+                theme.light_square
+            }
+        };
+        for i in 0..8 {
+            let (x, y) = if options.flipped { (7 - i, 0) } else { (i, 7) };
+            let (left, top) = position((x, y));
+            writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" \
+                 font-size=\"{}\" text-anchor=\"end\" {}>{}</text>",
+                left + square * 0.95,
+                top + square * 0.95,
+                font_size,
+                paint("fill", label_color((x, y))),
+                &square_name(x, y)[..1]
+            )
+            .unwrap();
+
+            let (x, y) = if options.flipped { (7, i) } else { (0, i) };
+            let (left, top) = position((x, y));
+            writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" \
+                 font-size=\"{}\" {}>{}</text>",
+                left + square * 0.05,
+                top + square * 0.05 + font_size,
+                font_size,
+                paint("fill", label_color((x, y))),
+                &square_name(x, y)[1..]
+            )
+            .unwrap();
+        }
+    }
+
+    // every sprite is embedded once and referenced by the squares using it
+    let mut defined = HashSet::new();
+    for x in 0..8 {
+        for y in 0..8 {
+            let figure = match field.get(x, y) {
+                Some(figure) => figure,
+                None => continue,
+            };
+            let name = assets::sprite_name(figure);
+            if defined.insert(name.clone()) {
+                let href = match (&options.piece_url, &theme.pieces) {
+                    (Some(url), _) => escape(&format!("{}{}", url, name)),
+                    (None, Some(dir)) => {
+                        let path = dir.join(&name);
+                        let bytes = fs::read(&path).map_err(|e| {
+                            format!("could not read {}: {}", path.display(), e)
+                        })?;
+                        format!("data:image/png;base64,{}", base64(&bytes))
+                    }
+                    (None, None) => {
+                        let bytes = assets::sprite(&name)
+                            .ok_or(format!("no embedded sprite {}", name))?;
+                        format!("data:image/png;base64,{}", base64(bytes))
+                    }
+                };
+                writeln!(
+                    svg,
+                    "<defs><image id=\"{}\" width=\"{}\" height=\"{}\" \
+                     xlink:href=\"{}\" preserveAspectRatio=\"none\"/></defs>",
+                    name.trim_end_matches(".png"),
+                    square * 0.9,
+                    square * 0.9,
+                    href
+                )
+                .unwrap();
+            }
+
+            let (left, top) = position((x, y));
+            writeln!(
+                svg,
+                "<use xlink:href=\"#{}\" x=\"{}\" y=\"{}\"/>",
+                name.trim_end_matches(".png"),
+                left + square * 0.05,
+                top + square * 0.05
+            )
+            .unwrap();
+        }
+    }
+
+    let center = |square_position: (u32, u32)| {
+        let (left, top) = position(square_position);
+        (left + square / 2.0, top + square / 2.0)
+    };
+    for annotation in &options.annotations {
+        let (r, g, b) = annotation.color().rgb();
+        let color = Color::RGB(r, g, b);
+        match *annotation {
+            Annotation::Arrow { from, to, .. } => {
+                // same proportions as the arrows drawn on screen
+                let start = center(from);
+                let tip = center(to);
+                let (dx, dy) = (tip.0 - start.0, tip.1 - start.1);
+                let length = (dx * dx + dy * dy).sqrt();
+                let (ux, uy) = (dx / length, dy / length);
+                let (nx, ny) = (-uy, ux);
+
+                let head_base =
+                    (tip.0 - ux * square * 0.4, tip.1 - uy * square * 0.4);
+                let shaft = square * 0.06;
+                let head = square * 0.2;
+                let points = [
+                    (start.0 + nx * shaft, start.1 + ny * shaft),
+                    (head_base.0 + nx * shaft, head_base.1 + ny * shaft),
+                    (head_base.0 + nx * head, head_base.1 + ny * head),
+                    tip,
+                    (head_base.0 - nx * head, head_base.1 - ny * head),
+                    (head_base.0 - nx * shaft, head_base.1 - ny * shaft),
+                    (start.0 - nx * shaft, start.1 - ny * shaft),
+                ];
+                let points: Vec<String> = points
+                    .iter()
+                    .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+                    .collect();
+                writeln!(
+                    svg,
+                    "<polygon points=\"{}\" {}/>",
+                    points.join(" "),
+                    paint("fill", color)
+                )
+                .unwrap();
+            }
+            Annotation::Circle {
+                square: circled, ..
+            } => {
+                let (cx, cy) = center(circled);
+                writeln!(
+                    svg,
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" \
+                     stroke-width=\"{}\" {}/>",
+                    cx,
+                    cy,
+                    square * 0.43,
+                    square * 0.08,
+                    paint("stroke", color)
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
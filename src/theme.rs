@@ -0,0 +1,155 @@
+use sdl2::pixels::Color;
+use serde::Deserialize;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    // board colors
+    pub light_square: Color,
+    pub dark_square: Color,
+    pub last_move: Color,
+    pub check: Color,
+    // interface colors
+    pub background: Color,
+    pub foreground: Color,
+    pub aux: Color,
+    pub highlight: Color,
+    // directory with the w_pawn.png, b_king.png, ... sprites and the font
+    // file, the embedded ones are used when not set
+    pub pieces: Option<PathBuf>,
+    pub font: Option<PathBuf>,
+    // directory with move.wav, capture.wav, check.wav and game_over.wav,
+    // beeps are played when not set
+    pub sounds: Option<PathBuf>,
+}
+
+impl Default for Theme {
+    // the original grey look of the game
+    fn default() -> Self {
+        Self {
+            name: "Classic".to_string(),
+            light_square: Color::RGB(170, 170, 170),
+            dark_square: Color::RGB(50, 50, 50),
+            last_move: Color::RGBA(230, 200, 60, 110),
+            check: Color::RGBA(230, 30, 30, 60),
+            background: Color::RGB(50, 50, 50),
+            foreground: Color::RGB(170, 170, 170),
+            aux: Color::RGB(100, 100, 100),
+            highlight: Color::RGB(255, 92, 51),
+            pieces: None,
+            font: None,
+            sounds: None,
+        }
+    }
+}
+
+// a theme file holds any number of `[[theme]]` tables, every key but the
+// name is optional and falls back to the classic theme
+#[derive(Deserialize)]
+struct ThemeFile {
+    #[serde(default)]
+    theme: Vec<ThemeEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeEntry {
+    name: String,
+    light_square: Option<String>,
+    dark_square: Option<String>,
+    last_move: Option<String>,
+    check: Option<String>,
+    background: Option<String>,
+    foreground: Option<String>,
+    aux: Option<String>,
+    highlight: Option<String>,
+    pieces: Option<PathBuf>,
+    font: Option<PathBuf>,
+    sounds: Option<PathBuf>,
+}
+
+// parses "#rrggbb" or "#rrggbbaa"
+fn parse_color(text: &str) -> Option<Color> {
+    let hex = text.strip_prefix('#')?;
+    if (hex.len() != 6 && hex.len() != 8) || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel =
+        |i: usize| u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok();
+    let alpha = if hex.len() == 8 { channel(3)? } else { 255 };
+    Some(Color::RGBA(channel(0)?, channel(1)?, channel(2)?, alpha))
+}
+
+impl ThemeEntry {
+    // paths are relative to the directory of the theme file
+    fn into_theme(self, base: &Path) -> Result<Theme, String> {
+        let default = Theme::default();
+        let name = self.name;
+        let color =
+            |value: Option<String>, fallback: Color, key: &str| match value {
+                Some(value) => parse_color(&value).ok_or_else(|| {
+                    format!(
+                        "theme \"{}\": invalid {} color {}",
+                        name, key, value
+                    )
+                }),
+                None => Ok(fallback),
+            };
+
+        Ok(Theme {
+            light_square: color(
+                self.light_square,
+                default.light_square,
+                "light_square",
+            )?,
+            dark_square: color(
+                self.dark_square,
+                default.dark_square,
+                "dark_square",
+            )?,
+            last_move: color(self.last_move, default.last_move, "last_move")?,
+            check: color(self.check, default.check, "check")?,
+            background: color(
+                self.background,
+                default.background,
+                "background",
+            )?,
+            foreground: color(
+                self.foreground,
+                default.foreground,
+                "foreground",
+            )?,
+            aux: color(self.aux, default.aux, "aux")?,
+            highlight: color(self.highlight, default.highlight, "highlight")?,
+            pieces: self.pieces.map(|pieces| base.join(pieces)),
+            font: self.font.map(|font| base.join(font)),
+            sounds: self.sounds.map(|sounds| base.join(sounds)),
+            name,
+        })
+    }
+}
+
+pub fn load_themes(path: &Path) -> Result<Vec<Theme>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    parse_themes(&content, base)
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// parses a theme file, relative paths in it are resolved against `base`
+pub fn parse_themes(content: &str, base: &Path) -> Result<Vec<Theme>, String> {
+    let file: ThemeFile = toml::from_str(content).map_err(|e| e.to_string())?;
+    if file.theme.is_empty() {
+        return Err("no themes found".to_string());
+    }
+
+    file.theme
+        .into_iter()
+        .map(|entry| entry.into_theme(base))
+        .collect()
+}